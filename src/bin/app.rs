@@ -4,34 +4,117 @@ fn main() {
     {
         use fisherman::level::InputResult;
         use fisherman::cli::CLI;
+        use fisherman::driver::{Driver, UserInterface};
         use fisherman::level::Game;
-        let mut game = Game::new();
-        let mut cli = CLI::new();
-        loop {
-            cli.render(&game);
-            let input_res = loop {
-                let res = game.handle_action(cli.input().into());
-                if let InputResult::InvalidInput = res {
-                    CLI.invalid_input();
-                } else {
-                    break res
-                }
-            };
-            match input_res {
-                fisherman::level::InputResult::InvalidInput => unreachable!(),
-                fisherman::level::InputResult::Ok => {},
-                fisherman::level::InputResult::LevelPassed => {
-                    cli.prompt(format!("Level passed! score: {}", game.get_score()));
+        use fisherman::campaign::Campaign;
+        use fisherman::replay::Replay;
+        use fisherman::keymap::Keymap;
+        let mut seed = rand::random();
+        let mut campaign = Campaign::default();
+        let mut save_path = "fisherman.save".to_string();
+        let mut loaded = None;
+        let mut record_path = None;
+        let mut scores_path = "fisherman.scores".to_string();
+        let mut replay = None;
+        let mut keymap = Keymap::default();
+        let mut color = CLI::color_supported();
+        let mut args = std::env::args().skip(1);
+        while let Some(arg) = args.next() {
+            match (arg.as_str(), args.next()) {
+                ("--seed", Some(s)) => seed = s.parse().expect("seed should be an unsigned integer"),
+                ("--campaign", Some(path)) => {
+                    let json = std::fs::read_to_string(&path).unwrap_or_else(|e| panic!("cannot read {}: {}", path, e));
+                    campaign = Campaign::from_json(&json).unwrap_or_else(|e| {
+                        eprintln!("{}: {}", path, e);
+                        std::process::exit(1);
+                    });
                 },
-                fisherman::level::InputResult::LevelFailed => {
-                    cli.prompt(format!("Level failed! score: {}", game.get_score()));
-                    return;
+                ("--save", Some(path)) => save_path = path,
+                ("--load", Some(path)) => {
+                    let data = std::fs::read_to_string(&path).unwrap_or_else(|e| panic!("cannot read {}: {}", path, e));
+                    loaded = Some(Game::load(&data).unwrap_or_else(|e| {
+                        eprintln!("{}: {}", path, e);
+                        std::process::exit(1);
+                    }));
+                    save_path = path;
                 },
-                fisherman::level::InputResult::GamePassed => {
-                    cli.prompt(format!("Congrats! you win all levels! score: {}", game.get_score()));
-                    return;
+                ("--record", Some(path)) => record_path = Some(path),
+                ("--scores", Some(path)) => scores_path = path,
+                ("--keymap", Some(path)) => {
+                    let json = std::fs::read_to_string(&path).unwrap_or_else(|e| panic!("cannot read {}: {}", path, e));
+                    keymap = Keymap::from_json(&json).unwrap_or_else(|e| {
+                        eprintln!("{}: {}", path, e);
+                        std::process::exit(1);
+                    });
                 },
+                ("--color", Some(when)) => color = match when.as_str() {
+                    "auto" => CLI::color_supported(),
+                    "always" => true,
+                    "never" => false,
+                    _ => {
+                        eprintln!("--color should be auto, always or never");
+                        std::process::exit(1);
+                    },
+                },
+                ("--replay", Some(path)) => {
+                    let json = std::fs::read_to_string(&path).unwrap_or_else(|e| panic!("cannot read {}: {}", path, e));
+                    replay = Some(Replay::from_json(&json).unwrap_or_else(|e| {
+                        eprintln!("{}: {}", path, e);
+                        std::process::exit(1);
+                    }));
+                },
+                _ => {
+                    eprintln!("usage: app [--seed <seed>] [--campaign <file>] [--save <file>] [--load <file>] [--record <file>] [--replay <file>] [--scores <file>] [--keymap <file>] [--color <auto|always|never>]");
+                    std::process::exit(1);
+                },
+            }
+        }
+        if let Some(replay) = replay {
+            // step through the recorded game, one turn per key press
+            let mut game = replay.new_game();
+            let mut cli = CLI::new(keymap, color, None);
+            cli.start();
+            // the terminal has to be restored before exiting
+            let fail = |cli: CLI, msg: String| -> ! {
+                drop(cli);
+                eprintln!("{}", msg);
+                std::process::exit(1);
+            };
+            for (i, turn) in replay.turns().iter().enumerate() {
+                if let Err(e) = replay.prepare_turn(&mut game, i) {
+                    fail(cli, format!("Replay {}", e));
+                }
+                cli.render(&game);
+                cli.prompt(format!("\nTurn {}/{}: {}", i + 1, replay.get_turn_count(), turn.action));
+                if !cli.pause() {
+                    return;
+                }
+                let res = game.handle_action(turn.action.into());
+                if res != turn.result {
+                    fail(cli, format!("Replay diverged: recorded {:?}, got {:?}", turn.result, res));
+                }
+                if res != InputResult::Ok {
+                    cli.prompt(format!("{:?}! score: {}", res, game.get_score()));
+                }
+            }
+            if let Err(e) = replay.prepare_turn(&mut game, replay.get_turn_count()) {
+                fail(cli, format!("Replay {}", e));
             }
+            cli.render(&game);
+            cli.prompt("\nEnd of replay".to_string());
+            return;
         }
+        let game = loaded.unwrap_or_else(|| Game::with_campaign(&campaign, seed));
+        let record = |game: &Game| {
+            if let Some(path) = &record_path {
+                if let Err(e) = std::fs::write(path, game.get_replay().to_json()) {
+                    eprintln!("Cannot write replay to {}: {}", path, e);
+                }
+            }
+        };
+        let mut cli = CLI::new(keymap, color, Some(scores_path));
+        let mut driver = Driver::new(game, Some(save_path));
+        driver.run(&mut cli);
+        record(driver.game());
     }
 }
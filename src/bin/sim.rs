@@ -0,0 +1,46 @@
+use fisherman::{campaign::Campaign, policy::{policy_by_name, POLICY_NAMES}, sim::{simulate_level, Summary}};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+fn main() {
+    let mut runs = 1000;
+    let mut seed = rand::random();
+    let mut max_turns = 1000;
+    let mut policy_name = "cautious".to_string();
+    let mut campaign = Campaign::default();
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match (arg.as_str(), args.next()) {
+            ("--runs", Some(n)) => runs = n.parse().expect("runs should be an unsigned integer"),
+            ("--seed", Some(s)) => seed = s.parse().expect("seed should be an unsigned integer"),
+            ("--max-turns", Some(n)) => max_turns = n.parse().expect("max turns should be an unsigned integer"),
+            ("--policy", Some(name)) => policy_name = name,
+            ("--campaign", Some(path)) => {
+                let json = std::fs::read_to_string(&path).unwrap_or_else(|e| panic!("cannot read {}: {}", path, e));
+                campaign = Campaign::from_json(&json).unwrap_or_else(|e| {
+                    eprintln!("{}: {}", path, e);
+                    std::process::exit(1);
+                });
+            },
+            _ => {
+                eprintln!("usage: sim [--runs <n>] [--seed <seed>] [--max-turns <n>] [--policy <{}>] [--campaign <file>]", POLICY_NAMES.join("|"));
+                std::process::exit(1);
+            },
+        }
+    }
+    if policy_by_name(&policy_name, 0).is_none() {
+        eprintln!("unknown policy {}, expected one of {}", policy_name, POLICY_NAMES.join(", "));
+        std::process::exit(1);
+    }
+
+    println!("seed {}, policy {}, {} runs per level, at most {} turns", seed, policy_name, runs, max_turns);
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    for (i, config) in campaign.levels.iter().enumerate() {
+        let mut summary = Summary::default();
+        for _ in 0..runs {
+            let mut policy = policy_by_name(&policy_name, rng.gen()).unwrap();
+            summary.add(&simulate_level(config, rng.gen(), max_turns, policy.as_mut()));
+        }
+        println!("level {}: {}", i, summary);
+    }
+}
@@ -0,0 +1,633 @@
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{clock::TimeOfDay, entities::Fisherman, map::HexCoord, terrain::{Terrain, TerrainMap}};
+
+/// Spawn rate (expected creatures per edge cell per turn) as a function of the distance from the nearest harbor.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum SpawnCurve {
+    Constant { value: f32 },
+    /// `base + slope * distance`, never below 0
+    Linear { base: f32, slope: f32 },
+    /// linear interpolation between `(distance, value)` points, flat beyond the first and last point
+    Piecewise { points: Vec<(i32, f32)> },
+    /// `values[distance]`, the last value is used for farther cells
+    Table { values: Vec<f32> },
+}
+
+impl SpawnCurve {
+    pub fn eval(&self, distance: i32) -> f32 {
+        match self {
+            SpawnCurve::Constant { value } => *value,
+            SpawnCurve::Linear { base, slope } => f32::max(base + slope * distance as f32, 0.0),
+            SpawnCurve::Piecewise { points } => {
+                let (first, last) = (points[0], points[points.len() - 1]);
+                if distance <= first.0 {
+                    return first.1;
+                }
+                if distance >= last.0 {
+                    return last.1;
+                }
+                let i = points.iter().position(|p| p.0 > distance).unwrap();
+                let ((d0, v0), (d1, v1)) = (points[i - 1], points[i]);
+                v0 + (v1 - v0) * (distance - d0) as f32 / (d1 - d0) as f32
+            },
+            SpawnCurve::Table { values } => values[usize::min(distance.max(0) as usize, values.len() - 1)],
+        }
+    }
+
+    fn validate(&self, field: &str) -> Result<(), CampaignError> {
+        let check_rate = |field: String, value: f32| {
+            if value.is_finite() && value >= 0.0 {
+                Ok(())
+            } else {
+                Err(CampaignError::invalid(field, format!("spawn rate must be a finite number >= 0, got {}", value)))
+            }
+        };
+        match self {
+            SpawnCurve::Constant { value } => check_rate(format!("{}.value", field), *value),
+            SpawnCurve::Linear { base, slope } => {
+                check_rate(format!("{}.base", field), *base)?;
+                if !slope.is_finite() {
+                    return Err(CampaignError::invalid(format!("{}.slope", field), format!("slope must be finite, got {}", slope)));
+                }
+                Ok(())
+            },
+            SpawnCurve::Piecewise { points } => {
+                if points.is_empty() {
+                    return Err(CampaignError::invalid(format!("{}.points", field), "at least one point is required"));
+                }
+                for (i, (distance, value)) in points.iter().enumerate() {
+                    if i > 0 && *distance <= points[i - 1].0 {
+                        return Err(CampaignError::invalid(format!("{}.points[{}]", field, i), "distances must be strictly increasing"));
+                    }
+                    check_rate(format!("{}.points[{}]", field, i), *value)?;
+                }
+                Ok(())
+            },
+            SpawnCurve::Table { values } => {
+                if values.is_empty() {
+                    return Err(CampaignError::invalid(format!("{}.values", field), "at least one value is required"));
+                }
+                for (i, value) in values.iter().enumerate() {
+                    check_rate(format!("{}.values[{}]", field, i), *value)?;
+                }
+                Ok(())
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LevelConfig {
+    pub target: usize,
+    pub initial_hp: i32,
+    pub attack_power: i32,
+    pub capture_success_rate: f32,
+    pub marlin_spawn_rate: SpawnCurve,
+    pub shark_spawn_rate: SpawnCurve,
+    /// where the boat can rest and deliver its catch
+    #[serde(default = "LevelConfig::default_harbors", skip_serializing_if = "LevelConfig::has_default_harbors")]
+    pub harbors: Vec<HexCoord>,
+    /// index of the harbor the boat sets out from
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub start_harbor: usize,
+    /// keep creatures alive out of sight instead of despawning them
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub world: Option<WorldConfig>,
+    #[serde(default, skip_serializing_if = "TerrainConfig::is_empty")]
+    pub terrain: TerrainConfig,
+    /// where creatures spawn, on top of the spawn curves
+    #[serde(default, skip_serializing_if = "OceanGenerator::is_uniform")]
+    pub ocean: OceanGenerator,
+    /// the level fails unless the boat is back in a harbor with its catch within this many turns
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub turn_limit: Option<usize>,
+    /// days and nights, it's always day without one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub day_cycle: Option<DayCycle>,
+    /// changing weather, it's always calm without one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub weather: Option<WeatherConfig>,
+}
+
+fn is_zero(n: &usize) -> bool {
+    *n == 0
+}
+
+impl LevelConfig {
+    pub(crate) fn default_harbors() -> Vec<HexCoord> {
+        vec![HexCoord::ZERO]
+    }
+
+    fn has_default_harbors(harbors: &Vec<HexCoord>) -> bool {
+        *harbors == Self::default_harbors()
+    }
+
+    /// Where the boat starts.
+    pub fn start(&self) -> HexCoord {
+        self.harbors[self.start_harbor]
+    }
+
+    fn validate(&self, field: &str) -> Result<(), CampaignError> {
+        if self.target == 0 {
+            return Err(CampaignError::invalid(format!("{}.target", field), "target must be at least 1"));
+        }
+        if self.initial_hp <= 0 {
+            return Err(CampaignError::invalid(format!("{}.initial_hp", field), "initial HP must be positive"));
+        }
+        if self.attack_power <= 0 {
+            return Err(CampaignError::invalid(format!("{}.attack_power", field), "attack power must be positive"));
+        }
+        if !(0.0..=1.0).contains(&self.capture_success_rate) {
+            return Err(CampaignError::invalid(format!("{}.capture_success_rate", field), format!("capture success rate must be within [0, 1], got {}", self.capture_success_rate)));
+        }
+        self.marlin_spawn_rate.validate(&format!("{}.marlin_spawn_rate", field))?;
+        self.shark_spawn_rate.validate(&format!("{}.shark_spawn_rate", field))?;
+        if self.harbors.is_empty() {
+            return Err(CampaignError::invalid(format!("{}.harbors", field), "a level needs at least one harbor"));
+        }
+        for (i, harbor) in self.harbors.iter().enumerate() {
+            let field = format!("{}.harbors[{}]", field, i);
+            if harbor.q + harbor.r + harbor.s != 0 {
+                return Err(CampaignError::invalid(field, format!("q + r + s must be 0, got ({}, {}, {})", harbor.q, harbor.r, harbor.s)));
+            }
+            if self.harbors[..i].contains(harbor) {
+                return Err(CampaignError::invalid(field, "the same harbor is listed twice"));
+            }
+        }
+        if self.start_harbor >= self.harbors.len() {
+            return Err(CampaignError::invalid(format!("{}.start_harbor", field), format!("there are only {} harbors, got index {}", self.harbors.len(), self.start_harbor)));
+        }
+        if let Some(world) = &self.world {
+            world.validate(&format!("{}.world", field))?;
+        }
+        self.terrain.validate(&format!("{}.terrain", field), &self.harbors)?;
+        self.ocean.validate(&format!("{}.ocean", field))?;
+        if self.turn_limit == Some(0) {
+            return Err(CampaignError::invalid(format!("{}.turn_limit", field), "turn limit must be at least 1"));
+        }
+        if let Some(day_cycle) = &self.day_cycle {
+            day_cycle.validate(&format!("{}.day_cycle", field))?;
+        }
+        if let Some(weather) = &self.weather {
+            weather.validate(&format!("{}.weather", field))?;
+        }
+        Ok(())
+    }
+}
+
+/// Relative odds of each kind of weather, and how many turns a spell of weather lasts.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WeatherConfig {
+    #[serde(default)]
+    pub calm: f32,
+    #[serde(default)]
+    pub wind: f32,
+    #[serde(default)]
+    pub fog: f32,
+    #[serde(default)]
+    pub storm: f32,
+    #[serde(default = "WeatherConfig::default_min_turns")]
+    pub min_turns: usize,
+    #[serde(default = "WeatherConfig::default_max_turns")]
+    pub max_turns: usize,
+}
+
+impl WeatherConfig {
+    fn default_min_turns() -> usize {
+        5
+    }
+
+    fn default_max_turns() -> usize {
+        15
+    }
+
+    pub(crate) fn total(&self) -> f32 {
+        self.calm + self.wind + self.fog + self.storm
+    }
+
+    fn validate(&self, field: &str) -> Result<(), CampaignError> {
+        for (name, odds) in [("calm", self.calm), ("wind", self.wind), ("fog", self.fog), ("storm", self.storm)] {
+            if !(odds.is_finite() && odds >= 0.0) {
+                return Err(CampaignError::invalid(format!("{}.{}", field, name), format!("must be a finite number >= 0, got {}", odds)));
+            }
+        }
+        if self.total() <= 0.0 {
+            return Err(CampaignError::invalid(field, "at least one kind of weather must have odds above 0"));
+        }
+        if self.min_turns == 0 {
+            return Err(CampaignError::invalid(format!("{}.min_turns", field), "weather must last at least 1 turn"));
+        }
+        if self.max_turns < self.min_turns {
+            return Err(CampaignError::invalid(format!("{}.max_turns", field), format!("must be at least min_turns ({}), got {}", self.min_turns, self.max_turns)));
+        }
+        Ok(())
+    }
+}
+
+/// Days of `day_turns` turns alternate with nights of `night_turns` turns. A level starts `start_turn` turns into the first day.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DayCycle {
+    pub day_turns: usize,
+    pub night_turns: usize,
+    #[serde(default)]
+    pub start_turn: usize,
+}
+
+impl DayCycle {
+    fn phase(&self, turn: usize) -> usize {
+        (turn + self.start_turn) % (self.day_turns + self.night_turns)
+    }
+
+    pub fn time_at(&self, turn: usize) -> TimeOfDay {
+        if self.phase(turn) < self.day_turns {
+            TimeOfDay::Day
+        } else {
+            TimeOfDay::Night
+        }
+    }
+
+    /// Turns until day breaks or night falls.
+    pub fn turns_until_change(&self, turn: usize) -> usize {
+        let phase = self.phase(turn);
+        if phase < self.day_turns {
+            self.day_turns - phase
+        } else {
+            self.day_turns + self.night_turns - phase
+        }
+    }
+
+    fn validate(&self, field: &str) -> Result<(), CampaignError> {
+        if self.day_turns == 0 {
+            return Err(CampaignError::invalid(format!("{}.day_turns", field), "days must last at least 1 turn"));
+        }
+        if self.night_turns == 0 {
+            return Err(CampaignError::invalid(format!("{}.night_turns", field), "nights must last at least 1 turn"));
+        }
+        Ok(())
+    }
+}
+
+/// Names the generator that decides where marlins and sharks spawn.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum OceanGenerator {
+    /// spawn rates only depend on the distance from the nearest harbor
+    #[default]
+    Uniform,
+    Noise(NoiseOcean),
+}
+
+/// Smooth random depth, richness and shark fields: marlins crowd in rich hot spots and avoid deep trenches,
+/// sharks gather in their own zones and in the trenches.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct NoiseOcean {
+    /// rough size of a feature, in cells
+    pub scale: f32,
+    /// layers of finer detail
+    #[serde(default = "NoiseOcean::default_octaves")]
+    pub octaves: u32,
+    /// cells richer than this (from 0 to 1) are hot spots
+    pub hot_spot_level: f32,
+    /// marlin spawn rate multiplier in hot spots
+    pub hot_spot_boost: f32,
+    /// cells deeper than this (from 0 to 1) are trenches, where no marlin spawns
+    pub trench_level: f32,
+    /// cells sharkier than this (from 0 to 1) are shark zones
+    pub shark_zone_level: f32,
+    /// shark spawn rate multiplier in shark zones and trenches
+    pub shark_zone_boost: f32,
+}
+
+impl NoiseOcean {
+    fn default_octaves() -> u32 {
+        3
+    }
+}
+
+impl OceanGenerator {
+    fn is_uniform(&self) -> bool {
+        *self == OceanGenerator::Uniform
+    }
+
+    fn validate(&self, field: &str) -> Result<(), CampaignError> {
+        let OceanGenerator::Noise(noise) = self else {
+            return Ok(());
+        };
+        if !(noise.scale.is_finite() && noise.scale > 0.0) {
+            return Err(CampaignError::invalid(format!("{}.scale", field), format!("scale must be positive, got {}", noise.scale)));
+        }
+        if !(1..=8).contains(&noise.octaves) {
+            return Err(CampaignError::invalid(format!("{}.octaves", field), format!("octaves must be within [1, 8], got {}", noise.octaves)));
+        }
+        for (name, level) in [("hot_spot_level", noise.hot_spot_level), ("trench_level", noise.trench_level), ("shark_zone_level", noise.shark_zone_level)] {
+            if !(0.0..=1.0).contains(&level) {
+                return Err(CampaignError::invalid(format!("{}.{}", field, name), format!("must be within [0, 1], got {}", level)));
+            }
+        }
+        for (name, boost) in [("hot_spot_boost", noise.hot_spot_boost), ("shark_zone_boost", noise.shark_zone_boost)] {
+            if !(boost.is_finite() && boost >= 0.0) {
+                return Err(CampaignError::invalid(format!("{}.{}", field, name), format!("must be a finite number >= 0, got {}", boost)));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Fixed terrain cells, with everything else generated (or open water without a generator).
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TerrainConfig {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub cells: Vec<(HexCoord, Terrain)>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub generator: Option<TerrainGenerator>,
+}
+
+/// Scatters terrain at random: each cell is a reef, shallows, kelp or a current with these probabilities.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TerrainGenerator {
+    #[serde(default)]
+    pub reef: f32,
+    #[serde(default)]
+    pub shallows: f32,
+    #[serde(default)]
+    pub kelp: f32,
+    #[serde(default)]
+    pub current: f32,
+}
+
+impl TerrainConfig {
+    fn is_empty(&self) -> bool {
+        self.cells.is_empty() && self.generator.is_none()
+    }
+
+    fn validate(&self, field: &str, harbors: &[HexCoord]) -> Result<(), CampaignError> {
+        for (i, (coord, terrain)) in self.cells.iter().enumerate() {
+            let field = format!("{}.cells[{}]", field, i);
+            if coord.q + coord.r + coord.s != 0 {
+                return Err(CampaignError::invalid(field, format!("q + r + s must be 0, got ({}, {}, {})", coord.q, coord.r, coord.s)));
+            }
+            if harbors.contains(coord) && !terrain.boat_can_enter() {
+                return Err(CampaignError::invalid(field, "harbors must stay open"));
+            }
+            // a current would carry the boat out of the harbor every time it arrives
+            if harbors.contains(coord) && matches!(terrain, Terrain::Current(_)) {
+                return Err(CampaignError::invalid(field, "harbors must stay still"));
+            }
+            if let Terrain::Current(dir) = terrain {
+                if dir.index().is_none() {
+                    return Err(CampaignError::invalid(field, format!("a current must point to an adjacent cell, got ({}, {}, {})", dir.q, dir.r, dir.s)));
+                }
+            }
+        }
+        if let Some(generator) = &self.generator {
+            let fractions = [("reef", generator.reef), ("shallows", generator.shallows), ("kelp", generator.kelp), ("current", generator.current)];
+            for (name, fraction) in fractions {
+                if !(0.0..=1.0).contains(&fraction) {
+                    return Err(CampaignError::invalid(format!("{}.generator.{}", field, name), format!("must be within [0, 1], got {}", fraction)));
+                }
+            }
+            let total = fractions.iter().map(|(_, f)| f).sum::<f32>();
+            if total > 1.0 {
+                return Err(CampaignError::invalid(format!("{}.generator", field), format!("fractions add up to {}, more than 1", total)));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A persistent ocean: creatures out of sight keep existing as long as they stay within `radius` of a harbor.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WorldConfig {
+    /// distance from the nearest harbor beyond which nothing lives
+    pub radius: i32,
+    /// the world is split into `region_size` x `region_size` parallelograms of cells (in q, r)
+    pub region_size: i32,
+    /// nothing spawns in a region already holding this many creatures
+    pub region_cap: usize,
+}
+
+impl WorldConfig {
+    pub fn contains(&self, terrain: &TerrainMap, coord: &HexCoord) -> bool {
+        terrain.harbor_distance(coord) <= self.radius
+    }
+
+    pub fn region_of(&self, coord: &HexCoord) -> (i32, i32) {
+        (coord.q.div_euclid(self.region_size), coord.r.div_euclid(self.region_size))
+    }
+
+    fn validate(&self, field: &str) -> Result<(), CampaignError> {
+        if self.radius <= Fisherman::VISUAL_RADIUS {
+            return Err(CampaignError::invalid(format!("{}.radius", field), format!("world radius must be larger than the fisherman's sight ({})", Fisherman::VISUAL_RADIUS)));
+        }
+        if self.region_size <= 0 {
+            return Err(CampaignError::invalid(format!("{}.region_size", field), "region size must be positive"));
+        }
+        Ok(())
+    }
+}
+
+/// An ordered list of levels, played one after another.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Campaign {
+    pub levels: Vec<LevelConfig>,
+}
+
+impl Campaign {
+    pub fn from_json(json: &str) -> Result<Self, CampaignError> {
+        let campaign: Campaign = serde_json::from_str(json).map_err(|e| CampaignError::Syntax {
+            line: e.line(),
+            column: e.column(),
+            message: match Self::failing_level(json) {
+                Some(i) => format!("levels[{}]: {}", i, e),
+                None => e.to_string(),
+            },
+        })?;
+        campaign.validate()?;
+        Ok(campaign)
+    }
+
+    // the level a well-formed file fails to be read at, found by reading the levels one by one
+    fn failing_level(json: &str) -> Option<usize> {
+        let value: serde_json::Value = serde_json::from_str(json).ok()?;
+        value.get("levels")?.as_array()?.iter().position(|level| serde_json::from_value::<LevelConfig>(level.clone()).is_err())
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+
+    pub fn validate(&self) -> Result<(), CampaignError> {
+        if self.levels.is_empty() {
+            return Err(CampaignError::invalid("levels", "a campaign needs at least one level"));
+        }
+        for (i, level) in self.levels.iter().enumerate() {
+            level.validate(&format!("levels[{}]", i))?;
+        }
+        Ok(())
+    }
+}
+
+impl Default for Campaign {
+    fn default() -> Self {
+        Self::from_json(include_str!("../campaigns/default.json")).expect("built-in campaign is invalid")
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CampaignError {
+    /// the file is not valid JSON or doesn't match the campaign format
+    Syntax { line: usize, column: usize, message: String },
+    /// the file is well-formed, but `field` has a value the game can't use
+    Invalid { field: String, message: String },
+}
+
+impl CampaignError {
+    fn invalid(field: impl Into<String>, message: impl Into<String>) -> Self {
+        Self::Invalid { field: field.into(), message: message.into() }
+    }
+}
+
+impl Display for CampaignError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CampaignError::Syntax { message, .. } => write!(f, "malformed campaign: {}", message),
+            CampaignError::Invalid { field, message } => write!(f, "invalid campaign field `{}`: {}", field, message),
+        }
+    }
+}
+
+impl std::error::Error for CampaignError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn piecewise_curve_interpolates_between_points() {
+        let curve = SpawnCurve::Piecewise { points: vec![(0, 1.0), (4, 0.0), (6, 2.0)] };
+        for (distance, value) in [(-2, 1.0), (0, 1.0), (1, 0.75), (2, 0.5), (4, 0.0), (5, 1.0), (6, 2.0), (20, 2.0)] {
+            assert_eq!(curve.eval(distance), value, "at {}", distance);
+        }
+    }
+
+    #[test]
+    fn table_curve_repeats_its_last_value() {
+        let curve = SpawnCurve::Table { values: vec![0.5, 0.25, 0.0] };
+        for (distance, value) in [(-3, 0.5), (0, 0.5), (1, 0.25), (2, 0.0), (9, 0.0)] {
+            assert_eq!(curve.eval(distance), value, "at {}", distance);
+        }
+        assert_eq!(SpawnCurve::Linear { base: 1.0, slope: -0.5 }.eval(3), 0.0);
+        assert_eq!(SpawnCurve::Constant { value: 0.3 }.eval(100), 0.3);
+    }
+
+    #[test]
+    fn validation_errors_name_the_field() {
+        type BreakLevel = fn(&mut LevelConfig);
+        let cases: [(&str, BreakLevel); 22] = [
+            ("levels[1].target", |l| l.target = 0),
+            ("levels[1].initial_hp", |l| l.initial_hp = 0),
+            ("levels[1].attack_power", |l| l.attack_power = -1),
+            ("levels[1].capture_success_rate", |l| l.capture_success_rate = 1.5),
+            ("levels[1].marlin_spawn_rate.value", |l| l.marlin_spawn_rate = SpawnCurve::Constant { value: -0.1 }),
+            ("levels[1].shark_spawn_rate.base", |l| l.shark_spawn_rate = SpawnCurve::Linear { base: f32::NAN, slope: 0.0 }),
+            ("levels[1].shark_spawn_rate.slope", |l| l.shark_spawn_rate = SpawnCurve::Linear { base: 0.1, slope: f32::INFINITY }),
+            ("levels[1].marlin_spawn_rate.points", |l| l.marlin_spawn_rate = SpawnCurve::Piecewise { points: vec![] }),
+            ("levels[1].marlin_spawn_rate.points[1]", |l| l.marlin_spawn_rate = SpawnCurve::Piecewise { points: vec![(2, 0.1), (2, 0.2)] }),
+            ("levels[1].marlin_spawn_rate.points[0]", |l| l.marlin_spawn_rate = SpawnCurve::Piecewise { points: vec![(2, -0.1)] }),
+            ("levels[1].marlin_spawn_rate.values", |l| l.marlin_spawn_rate = SpawnCurve::Table { values: vec![] }),
+            ("levels[1].marlin_spawn_rate.values[2]", |l| l.marlin_spawn_rate = SpawnCurve::Table { values: vec![0.1, 0.1, -1.0] }),
+            ("levels[1].harbors", |l| l.harbors = vec![]),
+            ("levels[1].harbors[0]", |l| l.harbors = vec![HexCoord { q: 1, r: 1, s: 1 }]),
+            ("levels[1].harbors[1]", |l| l.harbors = vec![HexCoord::ZERO, HexCoord::ZERO]),
+            ("levels[1].start_harbor", |l| l.start_harbor = 1),
+            ("levels[1].world.radius", |l| l.world = Some(WorldConfig { radius: 1, region_size: 4, region_cap: 4 })),
+            ("levels[1].world.region_size", |l| l.world = Some(WorldConfig { radius: 20, region_size: 0, region_cap: 4 })),
+            ("levels[1].terrain.cells[0]", |l| l.terrain.cells = vec![(HexCoord::ZERO, Terrain::Reef)]),
+            ("levels[1].terrain.generator.kelp", |l| l.terrain.generator = Some(TerrainGenerator { reef: 0.0, shallows: 0.0, kelp: 2.0, current: 0.0 })),
+            ("levels[1].terrain.generator", |l| l.terrain.generator = Some(TerrainGenerator { reef: 0.5, shallows: 0.5, kelp: 0.5, current: 0.0 })),
+            ("levels[1].turn_limit", |l| l.turn_limit = Some(0)),
+        ];
+        for (expected, break_level) in cases {
+            let mut campaign = Campaign::default();
+            break_level(&mut campaign.levels[1]);
+            match campaign.validate() {
+                Err(CampaignError::Invalid { field, .. }) => assert_eq!(field, expected),
+                other => panic!("expected an error at {}, got {:?}", expected, other),
+            }
+        }
+        assert_eq!(Campaign { levels: vec![] }.validate(), Err(CampaignError::invalid("levels", "a campaign needs at least one level")));
+    }
+
+    #[test]
+    fn structural_errors_name_the_level() {
+        let json = include_str!("../campaigns/default.json");
+        let mut value: serde_json::Value = serde_json::from_str(json).unwrap();
+        value["levels"][2]["speed"] = 3.into();
+        let err = Campaign::from_json(&serde_json::to_string_pretty(&value).unwrap()).unwrap_err();
+        assert!(matches!(&err, CampaignError::Syntax { message, line, .. } if message.starts_with("levels[2]: unknown field `speed`") && *line > 0), "{}", err);
+
+        value["levels"][2].as_object_mut().unwrap().remove("speed");
+        value["levels"][1].as_object_mut().unwrap().remove("target");
+        let err = Campaign::from_json(&value.to_string()).unwrap_err();
+        assert!(matches!(&err, CampaignError::Syntax { message, .. } if message.starts_with("levels[1]: missing field `target`")), "{}", err);
+
+        let err = Campaign::from_json("{ \"levels\": [").unwrap_err();
+        assert!(matches!(&err, CampaignError::Syntax { message, .. } if !message.starts_with("levels")), "{}", err);
+    }
+
+    #[test]
+    fn day_cycle_alternates_from_the_start_turn() {
+        use TimeOfDay::{Day, Night};
+        let cycle = DayCycle { day_turns: 3, night_turns: 2, start_turn: 1 };
+        let times = (0..7).map(|turn| cycle.time_at(turn)).collect::<Vec<_>>();
+        assert_eq!(times, [Day, Day, Night, Night, Day, Day, Day]);
+        let changes = (0..7).map(|turn| cycle.turns_until_change(turn)).collect::<Vec<_>>();
+        assert_eq!(changes, [2, 1, 2, 1, 3, 2, 1]);
+        // the last turn of a phase is always one turn from the change
+        for turn in 0..20 {
+            assert_eq!(cycle.turns_until_change(turn) == 1, cycle.time_at(turn) != cycle.time_at(turn + 1), "turn {}", turn);
+        }
+    }
+
+    #[test]
+    fn bad_weather_odds_are_rejected() {
+        let weather = WeatherConfig { calm: 1.0, wind: 1.0, fog: 0.0, storm: 0.0, min_turns: 3, max_turns: 6 };
+        let cases = [
+            ("levels[0].weather.fog", WeatherConfig { fog: -0.5, ..weather }),
+            ("levels[0].weather.storm", WeatherConfig { storm: f32::NAN, ..weather }),
+            ("levels[0].weather", WeatherConfig { calm: 0.0, wind: 0.0, ..weather }),
+            ("levels[0].weather.min_turns", WeatherConfig { min_turns: 0, ..weather }),
+            ("levels[0].weather.max_turns", WeatherConfig { max_turns: 2, ..weather }),
+        ];
+        for (expected, config) in cases {
+            let mut campaign = Campaign::default();
+            campaign.levels[0].weather = Some(config);
+            match campaign.validate() {
+                Err(CampaignError::Invalid { field, .. }) => assert_eq!(field, expected),
+                other => panic!("expected an error at {}, got {:?}", expected, other),
+            }
+        }
+        let mut campaign = Campaign::default();
+        campaign.levels[0].weather = Some(WeatherConfig { max_turns: 3, ..weather });
+        assert_eq!(campaign.validate(), Ok(()));
+    }
+
+    #[test]
+    fn current_on_harbor_is_rejected() {
+        let json = include_str!("../campaigns/terrain.json").replacen(r#""cells": ["#, r#""cells": [[{ "q": 0, "r": 0, "s": 0 }, { "current": { "q": 0, "r": 1, "s": -1 } }],"#, 1);
+        let err = Campaign::from_json(&json).unwrap_err();
+        assert!(matches!(&err, CampaignError::Invalid { message, .. } if message == "harbors must stay still"), "{}", err);
+    }
+}
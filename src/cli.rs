@@ -1,398 +1,602 @@
-use termion::input::TermRead;
+use crate::{driver::{Command, UserInterface}, frame::{Cell, Color, Frame, Style}, highscore::HighScores, keymap::{key_label, KeyAction, Keymap}, render, shop::Upgrade, entities::{ActionError, Fisherman}, level::{Game, LevelChange, UserAction}, map::HexCoord, policy::{CautiousHunter, Policy}};
 
-use crate::{entities::{Damageable, Fisherman}, level::{Game, UserAction}, map::HexDir};
+use std::fmt::{Display, Write as _};
+use std::io::{stdout, Stdout, Write};
+use std::sync::Arc;
+use std::time::Duration;
+use termion::{color, style, event::Key, input::{Keys, TermRead}, raw::{IntoRawMode, RawTerminal}, AsyncReader};
 
-use core::f32;
-use std::io::{stdin, stdout, Read, Write};
-use termion::{color, style};
+pub struct CLI {
+    // suggests moves for the hint and autopilot keys
+    autopilot: CautiousHunter,
+    keymap: Keymap,
+    // false on terminals without colors, frames are then drawn with marks instead
+    color: bool,
+    // where finished runs are added to the high scores, none to keep no table
+    scores_path: Option<String>,
+    // restores the terminal when dropped, the panic hook holds a weak reference to leave raw mode early
+    terminal: Arc<RawTerminal<Stdout>>,
+    keys: Keys<AsyncReader>,
+    size: (u16, u16),
+    // the lines on screen, as last presented
+    shown: Vec<String>,
+    screen_valid: bool,
+    // whether the cursor still sits after the last frame
+    after_frame: bool,
+}
 
-pub struct CLI;
+enum Input {
+    Key(Key),
+    Resize,
+}
 
 impl CLI {
-    fn render_map(game: &Game, fisherman: &Fisherman) {
-        // first line
-        const N_US: usize = 6;
-        const N_SLASH: usize = 2;
-        let order: usize = Fisherman::VISUAL_RADIUS as usize;
-
-        // let order = 0;
-        // beginning
-        for _ in 0..(order * (N_US + N_SLASH) + N_SLASH) { print!(" "); }
-        for _ in 0..N_US { print!("_"); }
-        println!();
-        for i in 0..order {
-            for j in 0..(N_SLASH - 1) {
-                
-                for _ in 0..((order - i) * (N_US + N_SLASH) + N_SLASH - j - 1) { print!(" "); }
-                for k in 0..=i {
-                    let q0 = -(i as i32) + 2 * (k as i32);
-                    let q1 = -(i as i32) + 2 * (k as i32) + 1;
-                    let r = (order as i32) - (k as i32);
-                    let s0: i32 = (i as i32) - (order as i32) - (k as i32);
-                    let s1 = (i as i32) - (order as i32) - (k as i32) - 1;
-                    let coord0 = fisherman.get_coord() + unsafe { HexDir::new_unchecked(q0, r, s0) };
-                    let coord1 = fisherman.get_coord() + unsafe { HexDir::new_unchecked(q1, r, s1) };
-                    let cell0_marlin = game.get_discovered_marlin_num_at(&coord0);
-                    let cell1_shark = game.get_shark_num_at(&coord1);
-                    print!("/");
-                    // 0th line: marlins
-                    if j == 0 && cell0_marlin > 0 {
-                        print!(" M{:>3} ", cell0_marlin);
-                    } else {
-                        // print!("{:>2}{:>2}{:>2}", coord0.get_q(), coord0.get_r(), coord0.get_s());
-                        print!("      ")
-                    }
-                    for _ in 0..(N_US - 6 + 2 * j) { print!(" "); }
-                    print!("\\");
-                    // 2nd line: shark
-                    if k != i {
-                        if j == 0 && cell1_shark > 0 {
-                            print!("  S{:>3}  ", cell1_shark);
-                        } else {
-                            // print!(" {:>2}{:>2}{:>2} ", coord1.get_q(), coord1.get_r(), coord1.get_s());
-                            print!("        ");
-                        }
-                        for _ in 0..(N_US + 2 * (N_SLASH - j - 1) - 8) { print!(" "); }
-                    }
-                }
-                println!();
+    fn render_shop(&mut self, game: &Game) {
+        self.println(format_args!("Shop ({} coins{}):", game.get_coins(), if game.is_shop_open() { "" } else { ", open in harbor only" }));
+        for upgrade in Upgrade::ALL {
+            let level = format!("{}/{}", game.get_upgrade_level(upgrade), upgrade.max_level());
+            match game.get_upgrade_price(upgrade) {
+                Some(price) => self.println(format_args!("  buy {:<11} {:>4} coins  level {}  {}", upgrade, price, level, upgrade.effect())),
+                None => self.println(format_args!("  {:<15} fully upgraded       {}", upgrade, upgrade.effect())),
             }
+        }
+    }
 
-            for _ in 0..((order - i - 1) * (N_US + N_SLASH) + N_SLASH) { print!(" "); }
-            for _ in 0..N_US { print!("_"); }
-            for k in 0..=i {
-                let q = -(i as i32) + 2 * (k as i32);
-                let r = (order as i32) - (k as i32);
-                let s = (i as i32) - (order as i32) - (k as i32);
-                let coord = fisherman.get_coord() + unsafe { HexDir::new_unchecked(q, r, s) };
-                print!("/");
-
-                if coord == fisherman.get_coord() && coord == Fisherman::HARBOR_COORD {
-                    print!("[Player]");
-                } else if coord  == Fisherman::HARBOR_COORD {
-                    print!("[||||||]");
-                } else if coord == fisherman.get_coord() {
-                    print!(" Player ");
-                } else {
-                    // print!(" {:>2}{:>2}{:>2} ", coord.get_q(), coord.get_r(), coord.get_s());
-                    print!("        ")
-                }
-                for _ in 0..(N_US + 2 * (N_SLASH - 1) - 8) { print!(" "); }
-                print!("\\");
-                for _ in 0..N_US { print!("_"); }
+    // where `:board` puts the plain text board, for bug reports
+    const BOARD_PATH: &'static str = "fisherman.board.txt";
+
+    // how often to check for key presses and a resized terminal while waiting
+    const POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+    // keys of the questions asked in between turns, the same with any keymap.
+    // the attack is confirmed with Enter or the keymap's attack key.
+    const NEXT_SHARK_KEYS: [Key; 3] = [Key::Char('n'), Key::Char('\t'), Key::Right];
+    const PREVIOUS_SHARK_KEYS: [Key; 3] = [Key::Char('p'), Key::BackTab, Key::Left];
+    const CANCEL_KEYS: [Key; 2] = [Key::Esc, Key::Char('c')];
+    const RETRY_KEY: Key = Key::Char('r');
+    const RESTART_KEY: Key = Key::Char('c');
+    const LEAVE_KEY: Key = Key::Char('q');
+
+    /// Whether the terminal is likely to show colors: it isn't a dumb one and `NO_COLOR` is not set.
+    pub fn color_supported() -> bool {
+        let term = std::env::var("TERM").unwrap_or_default();
+        std::env::var_os("NO_COLOR").is_none_or(|v| v.is_empty()) && !term.is_empty() && term != "dumb"
+    }
+
+    pub fn new(keymap: Keymap, color: bool, scores_path: Option<String>) -> Self {
+        let terminal = Arc::new(stdout().into_raw_mode().expect("the CLI needs a terminal"));
+        let raw = Arc::downgrade(&terminal);
+        let default_hook = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            // leave raw mode before the message is printed, unwinding only drops the terminal afterwards
+            if let Some(terminal) = raw.upgrade() {
+                let _ = terminal.suspend_raw_mode();
+                println!("{}{}", style::Reset, termion::cursor::Show);
             }
-            println!();
+            default_hook(info);
+        }));
+        CLI {
+            autopilot: CautiousHunter::new(rand::random()),
+            keymap,
+            color,
+            scores_path,
+            terminal,
+            keys: termion::async_stdin().keys(),
+            size: termion::terminal_size().unwrap_or((80, 24)),
+            shown: vec![],
+            screen_valid: false,
+            after_frame: false,
         }
-        // intermediate section
-        for i in 0..=order {
-            for j in 0..(N_SLASH - 1) {
-                for _ in 0..(N_SLASH - j - 1) { print!(" "); }
-                for k in 0..=order {
-
-                    let q0 = -(order as i32) + 2 * (k as i32);
-                    let q1 = -(order as i32) + 2 * (k as i32) + 1;
-                    let r = (order as i32) - (i as i32) - (k as i32);
-                    let s0: i32 = - (k as i32) + (i as i32);
-                    let s1 = - (k as i32) + (i as i32) - 1;
-                    let coord0 = fisherman.get_coord() + unsafe { HexDir::new_unchecked(q0, r, s0) };
-                    let coord1 = fisherman.get_coord() + unsafe { HexDir::new_unchecked(q1, r, s1) };
-                    let cell0_marlin = game.get_discovered_marlin_num_at(&coord0);
-                    let cell1_shark = game.get_shark_num_at(&coord1);
-                    print!("/");
-
-                    // 0th line: marlins
-                    if j == 0 && cell0_marlin > 0 {
-                        print!(" M{:>3} ", cell0_marlin);
-                    } else {
-                        // print!("{:>2}{:>2}{:>2}", coord0.get_q(), coord0.get_r(), coord0.get_s());
-                        print!("      ")
-                    }
-                    for _ in 0..(N_US - 6 + 2 * j) { print!(" "); }
-                    print!("\\");
-
-                    // 2nd line: shark
-                    if k != order {
-                        if j == 0 && cell1_shark > 0 {
-                            print!("  S{:>3}  ", cell1_shark);
-                        } else {
-                            // print!(" {:>2}{:>2}{:>2} ", coord1.get_q(), coord1.get_r(), coord1.get_s());
-                            print!("        ");
-                        }
-                        for _ in 0..(N_US + 2 * (N_SLASH - j - 1) - 8) { print!(" "); }
-                    }
-                }
-                println!();
-            }
-            for k in 0..=order {
-
-                let q = -(order as i32) + 2 * (k as i32);
-                let r = (order as i32) - (i as i32) - (k as i32);
-                let s: i32 = - (k as i32) + (i as i32);
-                let coord = fisherman.get_coord() + unsafe { HexDir::new_unchecked(q, r, s) };
-                print!("/");
-                if coord == fisherman.get_coord() && coord == Fisherman::HARBOR_COORD {
-                    print!("[Player]");
-                } else if coord  == Fisherman::HARBOR_COORD {
-                    print!("[||||||]");
-                } else if coord == fisherman.get_coord() {
-                    print!(" Player ");
-                } else {
-                    // print!(" {:>2}{:>2}{:>2} ", coord.get_q(), coord.get_r(), coord.get_s());
-                    print!("        ")
-                }
-                for _ in 0..(N_US + 2 * N_SLASH - 2 - 8) { print!(" "); }
-                print!("\\");
-                if k != order {
-                    for _ in 0..N_US { print!("_"); }
-                }
+    }
+
+    // generated from the keymap, so it always tells the truth
+    fn print_key_binds(&mut self) {
+        self.println("==============================  Key Binds  ==============================");
+        for (left, right) in [
+            (KeyAction::MoveUp, KeyAction::CaptureUp),
+            (KeyAction::MoveDown, KeyAction::CaptureDown),
+            (KeyAction::MoveUpleft, KeyAction::CaptureUpleft),
+            (KeyAction::MoveDownleft, KeyAction::CaptureDownleft),
+            (KeyAction::MoveUpright, KeyAction::CaptureUpright),
+            (KeyAction::MoveDownright, KeyAction::CaptureDownright),
+            (KeyAction::Stay, KeyAction::CaptureHere),
+            (KeyAction::Discover, KeyAction::Attack),
+            (KeyAction::Hint, KeyAction::Autopilot),
+            (KeyAction::Shop, KeyAction::Help),
+            (KeyAction::Command, KeyAction::Quit),
+        ] {
+            self.println(format_args!("  {:<13} ->  {:<20} |  {} ->  {}", self.keymap.label(left), left.description(), self.keymap.label(right), right.description()));
+        }
+        self.println(format_args!("  Commands after {}: save, retry, restart, level N, shop, buy NAME, board, quit", self.keymap.label(KeyAction::Command)));
+        self.println(format_args!("  Choosing a shark: {} ->  Next  |  {} ->  Previous", Self::labels(&Self::NEXT_SHARK_KEYS), Self::labels(&Self::PREVIOUS_SHARK_KEYS)));
+        self.println(format_args!("                     Enter, {} ->  Attack  |  {} ->  Cancel", self.keymap.label(KeyAction::Attack), Self::labels(&Self::CANCEL_KEYS)));
+        self.println(format_args!("  In the shop:       1-{} ->  Buy An Upgrade  |  Any Other Key ->  Leave", Upgrade::ALL.len()));
+        self.println(format_args!("  After a level:     {} ->  Retry  |  {} ->  Restart Campaign  |  {} ->  Quit", key_label(Self::RETRY_KEY), key_label(Self::RESTART_KEY), key_label(Self::LEAVE_KEY)));
+        self.println("                     a level number, then Enter ->  Jump To That Level");
+        self.println("=========================================================================");
+    }
+
+    fn is_quit(&self, key: Key) -> bool {
+        self.keymap.action(key) == Some(KeyAction::Quit)
+    }
+
+    // "N, Tab, →", like `Keymap::label`
+    fn labels(keys: &[Key]) -> String {
+        keys.iter().map(|k| key_label(*k)).collect::<Vec<_>>().join(", ")
+    }
+
+
+    // map and status line, with the attack target highlighted if there is one, and `prompt` below
+    fn draw(&mut self, game: &Game, attack_target: Option<HexCoord>, prompt: &str) {
+        let mut frame = if self.color { Frame::new() } else { Frame::monochrome() };
+        render::draw_game(&mut frame, game, attack_target).unwrap();
+        write!(frame, "{}", prompt).unwrap();
+        self.present(&frame);
+    }
+
+    // shows the frame in one write, redrawing only the lines that changed since the last one
+    fn present(&mut self, frame: &Frame) {
+        let shown = self.screen_valid.then_some(self.shown.as_slice());
+        let (out, shown) = Self::update_screen(shown, frame, self.size);
+        print!("{}", out);
+        self.flush();
+        self.screen_valid = shown.is_some();
+        self.shown = shown.unwrap_or_default();
+        self.after_frame = true;
+    }
+
+    // what to write to turn the `shown` lines (none if unknown) into `frame` on a terminal of `size` (columns, rows),
+    // and the lines on screen afterwards if they are still known
+    fn update_screen(shown: Option<&[String]>, frame: &Frame, size: (u16, u16)) -> (String, Option<Vec<String>>) {
+        let lines = frame.lines().iter().map(|line| Self::ansi_line(line)).collect::<Vec<_>>();
+        let (columns, rows) = (size.0 as usize, size.1 as usize);
+        // lines would wrap or scroll off screen, so rows can't be addressed: print it all, the terminal scrolls to show its end
+        if lines.len() > rows || frame.lines().iter().any(|line| line.len() > columns) {
+            let out = format!("{}{}{}", termion::clear::All, termion::cursor::Goto(1, 1), lines.join("\r\n"));
+            return (out, None);
+        }
+        let mut out = String::new();
+        let shown = shown.unwrap_or_else(|| {
+            out += termion::clear::All.as_ref();
+            &[]
+        });
+        for (row, line) in lines.iter().enumerate() {
+            if shown.get(row) != Some(line) {
+                out += &format!("{}{}{}", termion::cursor::Goto(1, row as u16 + 1), line, termion::clear::UntilNewline);
             }
-            println!();
-
-            for j in 0..(N_SLASH - 1) {
-                for _ in 0..j { print!(" "); }
-                for k in 0..=order {
-                    let q0 = -(order as i32) + 2 * (k as i32);
-                    let q1 = -(order as i32) + 2 * (k as i32) + 1;
-                    let r0 = (order as i32) - (i as i32) - (k as i32);
-                    let r1 = (order as i32) - (i as i32) - (k as i32) - 1;
-                    let s: i32 = -(k as i32) + (i as i32);
-                    let coord0 = fisherman.get_coord() + unsafe { HexDir::new_unchecked(q0, r0, s) };
-                    let coord1 = fisherman.get_coord() + unsafe { HexDir::new_unchecked(q1, r1, s) };
-                    let cell0_sharks = game.get_shark_num_at(&coord0);
-                    let cell1_marlins = game.get_discovered_marlin_num_at(&coord1);
-
-                    print!("\\");
-                    // 2nd line: shark
-                    if j == 0 && cell0_sharks > 0{
-                        print!("  S{:>3}  ", cell0_sharks);
-                    } else {
-                        // print!(" {:>2}{:>2}{:>2} ", coord0.get_q(), coord0.get_r(), coord0.get_s());
-                        print!("        ");
-                    }
-                    for _ in 0..(N_US + 2 * (N_SLASH - j - 1) - 8) { print!(" "); }
-                    
-                    print!("/");
-                    if k != order {
-
-                        // 0th line: marlins
-                        if j == 0 && cell1_marlins > 0 {
-                            print!(" M{:>3} ", cell1_marlins);
-                        } else {
-                            // print!("{:>2}{:>2}{:>2}", coord1.get_q(), coord1.get_r(), coord1.get_s());
-                            print!("      ")
-                        }
-                        for _ in 0..(N_US - 6 + 2 * j) { print!(" "); }
-                    }
-                }
-                println!();
+        }
+        if lines.len() < shown.len() {
+            out += &format!("{}{}", termion::cursor::Goto(1, lines.len() as u16 + 1), termion::clear::AfterCursor);
+        }
+        // prompts carry on where the frame ends
+        let last = frame.lines().len().max(1);
+        let column = frame.lines().last().map_or(0, |line| line.len());
+        out += &termion::cursor::Goto(column as u16 + 1, last as u16).to_string();
+        (out, Some(lines))
+    }
+
+    fn ansi_line(line: &[Cell]) -> String {
+        let mut out = String::new();
+        let mut current = Style::PLAIN;
+        for cell in line {
+            if cell.style != current {
+                out += &Self::ansi_style(cell.style);
+                current = cell.style;
             }
-            for _ in 0..(N_SLASH - 1) { print!(" "); }
-
-            for k in 0..=order {
-
-                let q = -(order as i32) + 2 * (k as i32) + 1;
-                let r = (order as i32) - (i as i32) - (k as i32) - 1;
-                let s: i32 = - (k as i32) + (i as i32);
-                let coord = fisherman.get_coord() + unsafe { HexDir::new_unchecked(q, r, s) };
-                print!("\\");
-                for _ in 0..N_US { print!("_"); }
-                print!("/");
-                if k != order {
-                    if coord == fisherman.get_coord() && coord == Fisherman::HARBOR_COORD {
-                        print!("[Player]");
-                    } else if coord  == Fisherman::HARBOR_COORD {
-                        print!("[||||||]");
-                    } else if coord == fisherman.get_coord() {
-                        print!(" Player ");
-                    } else {
-                        // print!(" {:>2}{:>2}{:>2} ", coord.get_q(), coord.get_r(), coord.get_s());
-                        print!("        ")
-                    }
-                    for _ in 0..(N_US + 2 * (N_SLASH - 1) - 8) { print!(" "); }
-                }
+            out.push(cell.ch);
+        }
+        if current != Style::PLAIN {
+            out += style::Reset.as_ref();
+        }
+        out
+    }
+
+    fn ansi_style(s: Style) -> String {
+        let mut out = style::Reset.to_string();
+        if s.bold {
+            out += style::Bold.as_ref();
+        }
+        if s.italic {
+            out += style::Italic.as_ref();
+        }
+        if let Some(fg) = Self::ansi_color(s.fg) {
+            out += &color::Fg(fg).to_string();
+        }
+        if let Some(bg) = Self::ansi_color(s.bg) {
+            out += &color::Bg(bg).to_string();
+        }
+        out
+    }
+
+    fn ansi_color(c: Color) -> Option<color::AnsiValue> {
+        let value = match c {
+            Color::Default => return None,
+            Color::Black => 0,
+            Color::Red => 1,
+            Color::Green => 2,
+            Color::Yellow => 3,
+            Color::Blue => 4,
+            Color::Magenta => 5,
+            Color::Cyan => 6,
+            Color::White => 7,
+            Color::Gray => 8,
+            Color::DarkRed => 52,
+            Color::DarkGreen => 22,
+            Color::DarkBlue => 17,
+            Color::DarkGray => 236,
+        };
+        Some(color::AnsiValue(value))
+    }
+
+    // anything printed outside of a frame means the next frame has to be drawn from scratch
+    fn print(&mut self, text: impl Display) {
+        let mut text = text.to_string();
+        // the frame's prompt is answered with a key, which raw mode doesn't echo
+        if self.after_frame {
+            text.insert(0, '\n');
+            self.after_frame = false;
+        }
+        // raw mode turns off the terminal's own "\n" -> "\r\n" translation
+        print!("{}", text.replace('\n', "\r\n"));
+        self.screen_valid = false;
+    }
+
+    fn println(&mut self, text: impl Display) {
+        self.print(format_args!("{}\n", text));
+    }
+
+    /// Lets the player cycle through the sharks in sight, closest first, and returns the attack on the chosen one.
+    fn select_shark(&mut self, game: &Game) -> Option<UserAction> {
+        let coord = game.get_fisherman().get_coord();
+        let mut sharks = coord.within_radius(Fisherman::VISUAL_RADIUS).into_iter()
+            .flat_map(|c| (0..game.get_shark_num_at(&c)).map(move |i| (c, i)))
+            .collect::<Vec<_>>();
+        sharks.sort_by_key(|(c, i)| (coord.distance(c), *c, *i));
+        if sharks.is_empty() {
+            self.print("No shark in sight. Operation: ");
+            self.flush();
+            return None;
+        }
+        let mut selected = 0;
+        loop {
+            let (target, index) = sharks[selected];
+            let hp = game.get_nth_shark_at(&target, index).map_or(0, |s| s.get_hp());
+            self.draw(game, Some(target), &format!("\nAttack shark {}/{} ({}NM away, HP {}): [n]ext, [p]revious, Enter or {} to attack, Esc to cancel: ", selected + 1, sharks.len(), coord.distance(&target), hp, self.keymap.label(KeyAction::Attack)));
+            let Input::Key(key) = self.read_input() else {
+                continue;
+            };
+            // the attack key first, a keymap may bind it to one of the other keys
+            if key == Key::Char('\n') || self.keymap.action(key) == Some(KeyAction::Attack) {
+                return Some(UserAction::Attack(target, index));
+            } else if Self::NEXT_SHARK_KEYS.contains(&key) {
+                selected = (selected + 1) % sharks.len();
+            } else if Self::PREVIOUS_SHARK_KEYS.contains(&key) {
+                selected = (selected + sharks.len() - 1) % sharks.len();
+            } else if Self::CANCEL_KEYS.contains(&key) || self.is_quit(key) {
+                self.render(game);
+                return None;
             }
-            println!();
         }
-        // ending
-        for i in 0..order {
-            for j in 0..(N_SLASH - 1) {
-                for _ in 0..((i + 1) * (N_US + N_SLASH) + j) { print!(" "); }
-                for k in 0..(order - i) {
-
-                    let q0 = -(order as i32) + 2 * (k as i32 + 1) + (i as i32) - 1;
-                    let q1 = -(order as i32) + 2 * (k as i32 + 1) + (i as i32);
-                    let r0 = -(i as i32) - (k as i32 + 1);
-                    let r1 = -(i as i32) - (k as i32 + 1) - 1;
-                    let s: i32 = (order as i32) - (k as i32 + 1) + 1;
-                    let coord0 = fisherman.get_coord() + unsafe { HexDir::new_unchecked(q0, r0, s) };
-                    let coord1 = fisherman.get_coord() + unsafe { HexDir::new_unchecked(q1, r1, s) };
-                    let cell0_sharks = game.get_shark_num_at(&coord0);
-                    let cell1_marlins = game.get_discovered_marlin_num_at(&coord1);
-                    print!("\\");
-
-                    // 2nd line: shark
-                    if j == 0 && cell0_sharks > 0 {
-                        print!("  S{:>3}  ", cell0_sharks);
-                    } else {
-                        // print!(" {:>2}{:>2}{:>2} ", coord0.get_q(), coord0.get_r(), coord0.get_s());
-                        print!("        ");
-                    }
-                    for _ in 0..(N_US + 2 * (N_SLASH - j - 1) - 8) { print!(" "); }
-                    print!("/");
-                    
-                    if k != order - i - 1 {
-                        // 0th line: marlins
-                        if j == 0 && cell1_marlins > 0{
-                            print!(" M{:>3} ", cell1_marlins);
-                        } else {
-                            // print!("{:>2}{:>2}{:>2}", coord1.get_q(), coord1.get_r(), coord1.get_s());
-                            print!("      ")
-                        }
-                        for _ in 0..(N_US - 6 + 2 * j) { print!(" "); }
-                    }
-                }
-                println!();
+    }
+
+    // waits for the next key press, or for the terminal to change size
+    fn read_input(&mut self) -> Input {
+        loop {
+            // a garbled escape sequence is dropped
+            if let Some(Ok(key)) = self.keys.next() {
+                return Input::Key(key);
             }
+            let size = termion::terminal_size().unwrap_or(self.size);
+            if size != self.size {
+                self.size = size;
+                self.screen_valid = false;
+                return Input::Resize;
+            }
+            std::thread::sleep(Self::POLL_INTERVAL);
+        }
+    }
 
-            for _ in 0..((i + 1) * (N_US + N_SLASH) + N_SLASH - 1) { print!(" "); }
-            for k in 0..(order - i) {
-
-                let q = -(order as i32) + 2 * (k as i32 + 1) + (i as i32);
-                let r = -(i as i32) - (k as i32 + 1) - 1;
-                let s: i32 = (order as i32) - (k as i32 + 1) + 1;
-                let coord = fisherman.get_coord() + unsafe { HexDir::new_unchecked(q, r, s) };
-                print!("\\");
-                for _ in 0..N_US { print!("_"); }
-                print!("/");
-
-                if k != order - i - 1 {
-                    if coord == fisherman.get_coord() && coord == Fisherman::HARBOR_COORD {
-                        print!("[Player]");
-                    } else if coord  == Fisherman::HARBOR_COORD {
-                        print!("[||||||]");
-                    } else if coord == fisherman.get_coord() {
-                        print!(" Player ");
-                    } else {
-                        // print!(" {:>2}{:>2}{:>2} ", coord.get_q(), coord.get_r(), coord.get_s());
-                        print!("        ")
-                    }
-                    for _ in 0..(N_US + 2 * (N_SLASH - 1) - 8) { print!(" "); }
-                }
+    fn read_key(&mut self) -> Key {
+        loop {
+            if let Input::Key(key) = self.read_input() {
+                return key;
             }
-            println!();
         }
-        println!();
     }
 
-    fn render_compass(fisherman: &Fisherman) -> String {
-        let dist = fisherman.get_coord().distance(&Fisherman::HARBOR_COORD);
-        if dist == 0 {
-            return "0NM from harbor".to_string()
+    // a line typed after the prompt, with its own echo and backspace since the terminal is raw; `None` on Esc
+    fn read_line(&mut self, mut line: String) -> Option<String> {
+        self.print(format_args!("{}", line));
+        self.flush();
+        loop {
+            match self.read_key() {
+                Key::Char('\n') => {
+                    self.println("");
+                    return Some(line);
+                },
+                Key::Esc | Key::Ctrl('c') => {
+                    self.println("");
+                    return None;
+                },
+                Key::Backspace if line.pop().is_some() => self.print("\x08 \x08"),
+                Key::Char(c) if !c.is_control() => {
+                    line.push(c);
+                    self.print(format_args!("{}", c));
+                },
+                _ => {},
+            }
+            self.flush();
         }
-        let dir = Fisherman::HARBOR_COORD - fisherman.get_coord();
-        let zero = dir.r;
-        let sixty = dir.q;
-        let x = (zero as f32) + (sixty as f32) / 2f32;
-        let y = (sixty as f32) * 3f32.sqrt() / 2f32;
-        let rad = f32::atan2(y, x); // (-PI, PI)
-        let rad = if rad < 0.0 { f32::consts::TAU + rad } else { rad };
-        let deg = rad / f32::consts::PI * 180f32;
-        let (arrow, dir) = match deg {
-            0.0..22.5 | 337.5..=360.0 => ("↑", "N"),
-            22.5..67.5   => ("↗", "NE"),
-            67.5..112.5  => ("→", "E"),
-            112.5..157.5 => ("↘", "SE"),
-            157.5..202.5 => ("↓", "S"),
-            202.5..247.5 => ("↙", "SW"),
-            247.5..292.5 => ("←", "W"),
-            292.5..337.5 => ("↖", "NW"),
-            _ => panic!("degree conversion failed")
+    }
+
+    // output goes to stdout as usual, the terminal is only held to restore it
+    fn flush(&self) {
+        self.terminal.lock().flush().unwrap();
+    }
+
+    fn prompt_operation(&mut self) {
+        self.print("Operation: ");
+        self.flush();
+    }
+
+
+    // lists the upgrades and lets the player pick one by its number
+    fn shop(&mut self, game: &Game) -> Option<Upgrade> {
+        self.render_shop(game);
+        self.print("Number to buy, any other key to leave: ");
+        self.flush();
+        let key = self.read_key();
+        self.println("");
+        let upgrade = match key {
+            Key::Char(c) => c.to_digit(10).and_then(|n| Upgrade::ALL.get((n as usize).checked_sub(1)?)).copied(),
+            _ => None,
         };
-        format!("{} {}°{:2>} {}NM from harbor", arrow, deg as i32, dir, dist)
-    }
-
-    pub fn new() -> Self {
-        println!("{}{}\n{}{}                         The Old Man and the Sea{}", termion::clear::All, termion::cursor::Goto(1, 1), color::Fg(color::Green), style::Bold, style::Reset);
-        println!("{}                                       by Endaytrer{}\n", style::Italic, style::Reset);
-        println!("============================== How to play ==============================");
-        println!("{}Objective{}: Capture at least TARGET marlins (as much as possible) and", style::Bold, style::Reset);
-        println!("return to harbor, avoid sharks to keep HP above 0.\n");
-        println!("Only {}discovered{} marlins are shown on map, all sharks are shown. Sharks", style::Bold, style::Reset);
-        println!("will chase you very closely!\n");
-        println!("==============================  Key Binds  ==============================");
-        println!("  W     ->      Move Up                   |  Shift + W ->     Capture Up");
-        println!("  X     ->      Move Down                 |  Shift + X ->     Capture Down");
-        println!("  Q     ->      Move Upleft               |  Shift + Q ->     Capture Upleft");
-        println!("  Z     ->      Move Downleft             |  Shift + Z ->     Capture Downleft");
-        println!("  E     ->      Move Upright              |  Shift + E ->     Capture Upright");
-        println!("  C     ->      Move Downright            |  Shift + C ->     Capture Downright");
-        println!("  S     ->      Stay In Place             |  Shift + S ->     Capture Current");
-        println!("  [Key] + Enter -> Commit Action          |");
-        println!("  Enter         -> Find Nearby Marlins    |");
-        println!("=========================================================================");
-        println!("\n                     {}PRESS ANY KEY TO CONTINUE{}", style::Bold, style::Reset);
-        let mut stdin = stdin().lock();
-        let mut byte = [0u8];
-        stdin.read_exact(&mut byte).unwrap();
-        CLI
-    }
-
-    pub fn render(&mut self, game: &Game) {
-        let target = game.get_target();
-        let fisherman = game.get_fisherman();
-        let mut heart_format = String::new();
-        for _ in 0..fisherman.get_hp() {
-            heart_format += "♥ "
-        }for _ in fisherman.get_hp()..fisherman.get_initial_hp() {
-            heart_format += "♡ "
+        if upgrade.is_none() {
+            self.prompt_operation();
         }
-        print!("{}{}", termion::clear::All, termion::cursor::Goto(1, 1));
-        Self::render_map(game, &fisherman);
-        if fisherman.get_captured_marlins() >= target {
-            print!("{}", color::Fg(color::Green));
+        upgrade
+    }
+
+    // the commands too long for a single key, typed after ':'
+    fn command_line(&mut self, game: &Game) -> Option<Command> {
+        let line = self.read_line(String::new())?;
+        let line = line.trim();
+        let command = match line {
+            "save" => Some(Command::Save),
+            "retry" => Some(Command::Change(LevelChange::Retry)),
+            "restart" => Some(Command::Change(LevelChange::RestartCampaign)),
+            "quit" => Some(Command::Quit),
+            "board" => {
+                match std::fs::write(Self::BOARD_PATH, render::render_game(game)) {
+                    Ok(()) => self.println(format_args!("Board written to {}", Self::BOARD_PATH)),
+                    Err(e) => self.println(format_args!("Cannot write {}: {}", Self::BOARD_PATH, e)),
+                }
+                None
+            },
+            "shop" => self.shop(game).map(|u| Command::Action(UserAction::Buy(u))),
+            _ if line.starts_with("buy ") => match Upgrade::from_name(line["buy ".len()..].trim()) {
+                Some(upgrade) => Some(Command::Action(UserAction::Buy(upgrade))),
+                None => {
+                    self.println(format_args!("Nothing like that in the shop, try: {}.", Upgrade::ALL.map(|u| u.name()).join(", ")));
+                    None
+                },
+            },
+            _ => match Self::parse_level(line) {
+                Some(index) => Some(Command::Change(LevelChange::JumpTo(index))),
+                None => {
+                    self.println("Invalid command.");
+                    None
+                },
+            },
+        };
+        if command.is_none() {
+            self.prompt_operation();
         }
-        print!("target: {}/{target}{}, {}, ", fisherman.get_captured_marlins(), style::Reset, Self::render_compass(&fisherman));
-        if fisherman.get_hp() as f32 / fisherman.get_initial_hp() as f32 <= 0.25 {
-            print!("{}", color::Fg(color::Red));
+        command
+    }
+
+    // "level 3" -> index 2
+    fn parse_level(line: &str) -> Option<usize> {
+        let n = line.strip_prefix("level")?.trim().parse::<usize>().ok()?;
+        n.checked_sub(1)
+    }
+
+    /// Asks for a name for the high-score table, defaults to the login name.
+    pub fn ask_name(&mut self) -> String {
+        let default = std::env::var("USER").unwrap_or_else(|_| "fisherman".to_string());
+        self.print(format_args!("Your name [{}]: ", default));
+        let line = self.read_line(String::new()).unwrap_or_default();
+        match line.trim() {
+            "" => default,
+            name => name.to_string(),
         }
-        print!("HP: {}{} Operation: ", heart_format, style::Reset);
-        stdout().flush().unwrap();
     }
-    
-    pub fn input(&mut self) -> UserAction {
-        let mut stdin = stdin().lock();
+}
+
+impl UserInterface for CLI {
+    fn start(&mut self) {
+        let green = if self.color { color::Fg(color::Green).to_string() } else { String::new() };
+        self.println(format_args!("{}{}\n{}{}                         The Old Man and the Sea{}", termion::clear::All, termion::cursor::Goto(1, 1), green, style::Bold, style::Reset));
+        self.println(format_args!("{}                                       by Endaytrer{}\n", style::Italic, style::Reset));
+        self.println("============================== How to play ==============================");
+        self.println(format_args!("{}Objective{}: Capture at least TARGET marlins (as much as possible) and", style::Bold, style::Reset));
+        self.println("return to harbor, avoid sharks to keep HP above 0.\n");
+        self.println(format_args!("Only {}discovered{} marlins are shown on map, all sharks are shown. Sharks", style::Bold, style::Reset));
+        self.println("will chase you very closely!\n");
+        self.println("Terrain: ^^^^ reef (blocks the boat), .... shallows (no sharks),");
+        self.println(";;;; kelp (hides marlins), ↗↗↗↗ current (carries the boat).");
+        self.println("Nights shorten your sight (~~~~ is out of sight) and make sharks bolder.");
+        self.println("Fog shortens your sight, wind carries marlins along, storms spoil");
+        self.println("your catches and blow the boat around.");
+        self.println("Some levels have a turn limit: be back in harbor before it runs out.");
+        self.println("Every marlin beyond the target earns coins for upgrades in the harbor shop.");
+        self.println("The legend under the map explains what the colors of the hexes mean.\n");
+        self.print_key_binds();
+        self.println(format_args!("\n                     {}PRESS ANY KEY TO CONTINUE{}", style::Bold, style::Reset));
+        self.flush();
+        self.read_key();
+    }
+
+    fn render(&mut self, game: &Game) {
+        self.draw(game, None, "Operation: ");
+    }
+
+    fn input(&mut self, game: &Game) -> Command {
         loop {
+            let key = match self.read_input() {
+                Input::Key(key) => key,
+                Input::Resize => {
+                    self.render(game);
+                    continue;
+                },
+            };
+            let Some(action) = self.keymap.action(key) else {
+                continue;
+            };
+            if let Some(action) = action.user_action() {
+                return Command::Action(action);
+            }
+            match action {
+                KeyAction::Attack => {
+                    if let Some(action) = self.select_shark(game) {
+                        return Command::Action(action);
+                    }
+                },
+                KeyAction::Hint => {
+                    let hint = self.autopilot.choose_action(&game.view());
+                    self.print(format_args!("Hint: {}. Operation: ", hint));
+                    self.flush();
+                },
+                KeyAction::Autopilot => return Command::Action(self.autopilot.choose_action(&game.view())),
+                KeyAction::Shop => {
+                    if let Some(upgrade) = self.shop(game) {
+                        return Command::Action(UserAction::Buy(upgrade));
+                    }
+                },
+                KeyAction::Help => {
+                    self.print_key_binds();
+                    self.prompt_operation();
+                },
+                KeyAction::Command => {
+                    self.print(":");
+                    if let Some(command) = self.command_line(game) {
+                        return command;
+                    }
+                },
+                KeyAction::Quit => {
+                    self.println("");
+                    return Command::Quit;
+                },
+                _ => {},
+            }
+        }
+    }
 
-            let line = stdin.read_line().unwrap().unwrap();
-            let line = line.trim();
-            match line {
-                "s" => return UserAction::Move(HexDir::ZERO),
-                "w" => return UserAction::Move(HexDir::NORTH),
-                "x" => return UserAction::Move(HexDir::SOUTH),
-                "q" => return UserAction::Move(HexDir::NORTHWEST),
-                "z" => return UserAction::Move(HexDir::SOUTHWEST),
-                "e" => return UserAction::Move(HexDir::NORTHEAST),
-                "c" => return UserAction::Move(HexDir::SOUTHEAST),
-
-                "" => return UserAction::Discover,
-
-                "S" => return UserAction::Capture(HexDir::ZERO),
-                "W" => return UserAction::Capture(HexDir::NORTH),
-                "X" => return UserAction::Capture(HexDir::SOUTH),
-                "Q" => return UserAction::Capture(HexDir::NORTHWEST),
-                "Z" => return UserAction::Capture(HexDir::SOUTHWEST),
-                "E" => return UserAction::Capture(HexDir::NORTHEAST),
-                "C" => return UserAction::Capture(HexDir::SOUTHEAST),
-                _ => println!("Invalid action.")
+    fn invalid_input(&mut self, error: &ActionError) {
+        self.println(format_args!("Invalid input: {}", error));
+    }
+
+    fn prompt(&mut self, msg: String) {
+        self.println(msg);
+    }
+
+    fn pause(&mut self) -> bool {
+        self.print("Press any key to continue");
+        self.flush();
+        let key = self.read_key();
+        !self.is_quit(key)
+    }
+
+    fn ask_level_change(&mut self, game: &Game) -> Option<LevelChange> {
+        loop {
+            self.print(format_args!("[r]etry level {} (retried {} times), restart [c]ampaign, jump to level N (1-{}), or [q]uit: ", game.get_level_index() + 1, game.get_retries_at(game.get_level_index()), game.get_furthest_level() + 1));
+            self.flush();
+            let choice = match self.read_key() {
+                key if key == Self::RETRY_KEY => return Some(LevelChange::Retry),
+                key if key == Self::RESTART_KEY => return Some(LevelChange::RestartCampaign),
+                key if key == Self::LEAVE_KEY || self.is_quit(key) => return None,
+                // a level number may have more digits, so it is typed as a line
+                Key::Char(c) if c.is_ascii_digit() => self.read_line(c.to_string()),
+                _ => None,
+            };
+            match choice.and_then(|n| n.trim().parse::<usize>().ok()).and_then(|n| n.checked_sub(1)) {
+                Some(index) if index <= game.get_furthest_level() => return Some(LevelChange::JumpTo(index)),
+                _ => self.println("\r\nInvalid choice."),
             }
         }
     }
-    
-    pub fn invalid_input(&mut self) {
-        println!("Invalid input!");
+
+    // adds the run to the high scores and shows the best ones
+    fn run_over(&mut self, game: &Game) {
+        let Some(path) = self.scores_path.clone() else {
+            return;
+        };
+        let mut scores = match std::fs::read_to_string(&path) {
+            Ok(json) => HighScores::from_json(&json).unwrap_or_else(|e| {
+                self.println(format_args!("{}: {}, starting a new table", path, e));
+                HighScores::new()
+            }),
+            Err(_) => HighScores::new(),
+        };
+        let name = self.ask_name();
+        let timestamp = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).map_or(0, |d| d.as_secs());
+        scores.add_game(game, name, timestamp);
+        if let Err(e) = std::fs::write(&path, scores.to_json()) {
+            self.println(format_args!("Cannot write high scores to {}: {}", path, e));
+        }
+        self.println("\n=============================== High Scores ===============================");
+        for line in scores.get_top_lines(10) {
+            self.println(line);
+        }
     }
-    pub fn prompt(&mut self, msg: String) {
-        println!("{}", msg);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(text: &str) -> Frame {
+        let mut frame = Frame::new();
+        write!(frame, "{}", text).unwrap();
+        frame
     }
 
-}
\ No newline at end of file
+    const SIZE: (u16, u16) = (20, 5);
+
+    #[test]
+    fn first_frame_clears_the_screen() {
+        let (out, shown) = CLI::update_screen(None, &frame("one\ntwo"), SIZE);
+        assert!(out.starts_with(&termion::clear::All.to_string()));
+        assert!(out.contains("one") && out.contains("two"));
+        assert_eq!(shown.unwrap(), ["one", "two"]);
+    }
+
+    #[test]
+    fn only_changed_lines_are_redrawn() {
+        let shown = ["one".to_string(), "two".to_string(), "three".to_string()];
+        let (out, _) = CLI::update_screen(Some(&shown), &frame("one\ntwo\nthree"), SIZE);
+        // just the cursor, back after the prompt
+        assert_eq!(out, termion::cursor::Goto(6, 3).to_string());
+
+        let (out, now) = CLI::update_screen(Some(&shown), &frame("one\n2\nthree"), SIZE);
+        assert_eq!(out, format!("{}2{}{}", termion::cursor::Goto(1, 2), termion::clear::UntilNewline, termion::cursor::Goto(6, 3)));
+        assert_eq!(now.unwrap(), ["one", "2", "three"]);
+
+        // what is left of a longer frame is cleared
+        let (out, _) = CLI::update_screen(Some(&shown), &frame("one"), SIZE);
+        assert_eq!(out, format!("{}{}{}", termion::cursor::Goto(1, 2), termion::clear::AfterCursor, termion::cursor::Goto(4, 1)));
+    }
+
+    #[test]
+    fn frame_larger_than_the_terminal_is_printed_whole() {
+        let shown = ["one".to_string()];
+        for text in ["1\n2\n3\n4\n5\n6", "a line wider than the terminal"] {
+            let (out, now) = CLI::update_screen(Some(&shown), &frame(text), SIZE);
+            assert_eq!(out, format!("{}{}{}", termion::clear::All, termion::cursor::Goto(1, 1), text.replace('\n', "\r\n")));
+            // the next frame has to start over
+            assert_eq!(now, None);
+        }
+        let (_, now) = CLI::update_screen(Some(&shown), &frame("1\n2\n3\n4\n5"), SIZE);
+        assert!(now.is_some());
+    }
+}
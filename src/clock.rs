@@ -0,0 +1,22 @@
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+/// Nights shorten the fisherman's sight and embolden sharks.
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum TimeOfDay {
+    #[default]
+    Day,
+    Night,
+}
+
+impl Display for TimeOfDay {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TimeOfDay::Day => write!(f, "day"),
+            TimeOfDay::Night => write!(f, "night"),
+        }
+    }
+}
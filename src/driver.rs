@@ -0,0 +1,253 @@
+use crate::{entities::ActionError, level::{Game, InputResult, LevelChange, UserAction}};
+
+/// What the player asked for: either a game action or a request to the front-end itself.
+#[derive(Debug, Clone, Copy)]
+pub enum Command {
+    Action(UserAction),
+    Save,
+    Change(LevelChange),
+    Quit,
+}
+
+/// A front-end the `Driver` can play a game through.
+pub trait UserInterface {
+    /// Greets the player before the first turn.
+    fn start(&mut self);
+    fn render(&mut self, game: &Game);
+    /// The player's next command, `game` is what they see.
+    fn input(&mut self, game: &Game) -> Command;
+    fn invalid_input(&mut self, error: &ActionError);
+    fn prompt(&mut self, msg: String);
+    /// Waits for the player to read what was prompted, false if they want to quit instead.
+    fn pause(&mut self) -> bool;
+    /// Asks what to do once the level is over, `None` means quit.
+    fn ask_level_change(&mut self, game: &Game) -> Option<LevelChange>;
+    /// The run is over: the campaign was won, a level failed, the campaign is about to restart or the player quits.
+    fn run_over(&mut self, game: &Game);
+}
+
+/// Plays a game turn by turn through any `UserInterface`, until the player quits.
+pub struct Driver {
+    game: Game,
+    // where `Command::Save` writes, saving is off without one
+    save_path: Option<String>,
+    // turns were played since the run was last over, so quitting right after a failed level doesn't end it twice
+    unfinished: bool,
+}
+
+impl Driver {
+    pub fn new(game: Game, save_path: Option<String>) -> Self {
+        Self { game, save_path, unfinished: false }
+    }
+
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+
+    pub fn into_game(self) -> Game {
+        self.game
+    }
+
+    pub fn run(&mut self, ui: &mut impl UserInterface) {
+        ui.start();
+        while self.turn(ui) {}
+        self.end_run(ui);
+    }
+
+    /// Plays one turn, or does whatever the player asked for instead. Returns false once they quit.
+    pub fn turn(&mut self, ui: &mut impl UserInterface) -> bool {
+        ui.render(&self.game);
+        let res = loop {
+            let action = match ui.input(&self.game) {
+                Command::Action(action) => action,
+                Command::Save => {
+                    self.save(ui);
+                    continue;
+                },
+                Command::Change(change) => return self.change_level(ui, Some(change)),
+                Command::Quit => return false,
+            };
+            let res = self.game.handle_action(action.into());
+            if res != InputResult::InvalidInput {
+                self.unfinished = true;
+                break res;
+            }
+            ui.invalid_input(&self.game.last_error().unwrap());
+        };
+        match res {
+            InputResult::InvalidInput => unreachable!(),
+            InputResult::Ok => true,
+            InputResult::LevelPassed => {
+                if let Some(score) = self.game.get_score_of(self.game.get_level_index() - 1) {
+                    ui.prompt(format!("\n{}", score));
+                }
+                ui.prompt(format!("Level passed! campaign score: {}, coins: {} (spend them in the harbor shop)", self.game.get_score(), self.game.get_coins()));
+                ui.pause()
+            },
+            InputResult::LevelFailed => {
+                let reason = if self.game.get_turns_left() == Some(0) { "Out of time" } else { "Level failed" };
+                ui.prompt(format!("{}! campaign score: {}", reason, self.game.get_score()));
+                self.end_run(ui);
+                let change = ui.ask_level_change(&self.game);
+                self.change_level(ui, change)
+            },
+            InputResult::GamePassed => {
+                for score in self.game.scores() {
+                    ui.prompt(format!("\n{}", score));
+                }
+                ui.prompt(format!("Congrats! you win all levels! campaign score: {}", self.game.get_score()));
+                self.end_run(ui);
+                let change = ui.ask_level_change(&self.game);
+                self.change_level(ui, change)
+            },
+        }
+    }
+
+    fn save(&mut self, ui: &mut impl UserInterface) {
+        let Some(path) = &self.save_path else {
+            ui.prompt("Saving is turned off.".to_string());
+            return;
+        };
+        match std::fs::write(path, self.game.save()) {
+            Ok(()) => ui.prompt(format!("Game saved to {}", path)),
+            Err(e) => ui.prompt(format!("Cannot save to {}: {}", path, e)),
+        }
+    }
+
+    // false to quit
+    fn change_level(&mut self, ui: &mut impl UserInterface, change: Option<LevelChange>) -> bool {
+        let Some(change) = change else {
+            return false;
+        };
+        // the scores are gone once it restarts
+        if change == LevelChange::RestartCampaign {
+            self.end_run(ui);
+        }
+        if let Err(e) = self.game.change_level(change) {
+            ui.prompt(e.to_string());
+        }
+        true
+    }
+
+    fn end_run(&mut self, ui: &mut impl UserInterface) {
+        if self.unfinished {
+            ui.run_over(&self.game);
+            self.unfinished = false;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{campaign::Campaign, map::HexDir, policy::CautiousHunter, ui::{HeadlessUI, ScriptedUI}};
+
+    fn driver() -> Driver {
+        Driver::new(Game::with_campaign(&Campaign::default(), 14), None)
+    }
+
+    #[test]
+    fn valid_move_takes_a_turn() {
+        let mut driver = driver();
+        let mut ui = ScriptedUI::new([Command::Action(UserAction::Move(HexDir::NORTH))], []);
+        assert!(driver.turn(&mut ui));
+        assert_eq!(driver.game().get_turn(), 1);
+        assert_eq!(ui.boards.len(), 1);
+        assert!(ui.last_board().unwrap().contains("[Player]"));
+        assert!(ui.messages.is_empty());
+    }
+
+    #[test]
+    fn refused_action_is_reported_and_asked_again() {
+        let mut driver = driver();
+        let mut ui = ScriptedUI::new([Command::Action(UserAction::Discover), Command::Action(UserAction::Move(HexDir::NORTH))], []);
+        assert!(driver.turn(&mut ui));
+        assert_eq!(ui.messages, ["Invalid input: This action is not allowed in harbor!"]);
+        assert_eq!(driver.game().get_turn(), 1);
+    }
+
+    #[test]
+    fn retry_starts_the_level_again() {
+        let mut driver = driver();
+        let mut ui = ScriptedUI::new([Command::Action(UserAction::Move(HexDir::NORTH)), Command::Change(LevelChange::Retry)], []);
+        assert!(driver.turn(&mut ui));
+        assert!(driver.turn(&mut ui));
+        assert_eq!(driver.game().get_turn(), 0);
+        assert_eq!(driver.game().get_level_index(), 0);
+        assert!(driver.game().is_shop_open());
+        assert_eq!(ui.boards.len(), 2);
+    }
+
+    #[test]
+    fn quits_once_the_script_runs_out() {
+        let mut driver = driver();
+        let mut ui = ScriptedUI::new([Command::Action(UserAction::Move(HexDir::NORTH)), Command::Save], []);
+        driver.run(&mut ui);
+        assert_eq!(driver.game().get_turn(), 1);
+        // one board per turn, the last one shown while waiting for the command that never came
+        assert_eq!(ui.boards.len(), 2);
+        assert_eq!(ui.messages, ["Saving is turned off."]);
+        assert!(!driver.turn(&mut ui));
+    }
+
+    fn one_level(target: usize, turn_limit: Option<usize>) -> Driver {
+        let mut campaign = Campaign::default();
+        campaign.levels.truncate(1);
+        campaign.levels[0].target = target;
+        campaign.levels[0].turn_limit = turn_limit;
+        Driver::new(Game::with_campaign(&campaign, 14), None)
+    }
+
+    #[test]
+    fn run_is_over_when_a_level_fails() {
+        // out of time after the first turn, wherever the boat is
+        let mut driver = one_level(1, Some(1));
+        let stay = Command::Action(UserAction::Move(HexDir::ZERO));
+        let mut ui = ScriptedUI::new([stay, stay], [Some(LevelChange::RestartCampaign), None]);
+        driver.run(&mut ui);
+        // neither restarting nor quitting right after a failed level ends it again
+        assert_eq!(ui.runs.len(), 2);
+        assert!(ui.runs.iter().all(|r| !r.won));
+    }
+
+    #[test]
+    fn run_is_over_when_the_campaign_is_won() {
+        // nothing to catch, a trip out and back wins
+        let mut driver = one_level(0, None);
+        let mut ui = ScriptedUI::new([Command::Action(UserAction::Move(HexDir::NORTH)), Command::Action(UserAction::Move(HexDir::SOUTH))], [None]);
+        driver.run(&mut ui);
+        assert_eq!(ui.runs.len(), 1);
+        assert!(ui.runs[0].won);
+    }
+
+    #[test]
+    fn run_is_over_when_restarted_or_quit() {
+        let mut driver = one_level(5, None);
+        let away = Command::Action(UserAction::Move(HexDir::NORTH));
+        let mut ui = ScriptedUI::new([away, Command::Change(LevelChange::RestartCampaign), away], []);
+        driver.run(&mut ui);
+        assert_eq!(ui.runs.len(), 2);
+        assert_eq!(ui.runs[1].score, 0);
+        // nothing played since the last run was over
+        let mut ui = ScriptedUI::new([Command::Change(LevelChange::RestartCampaign)], []);
+        driver.run(&mut ui);
+        assert!(ui.runs.is_empty());
+    }
+
+    #[test]
+    fn headless_soak_over_campaigns() {
+        const TURNS: usize = 400;
+        let dir = concat!(env!("CARGO_MANIFEST_DIR"), "/campaigns");
+        let mut paths = std::fs::read_dir(dir).unwrap().map(|e| e.unwrap().path()).collect::<Vec<_>>();
+        paths.sort();
+        assert!(!paths.is_empty());
+        for path in paths {
+            let campaign = Campaign::from_json(&std::fs::read_to_string(&path).unwrap()).unwrap_or_else(|e| panic!("{}: {}", path.display(), e));
+            let mut driver = Driver::new(Game::with_campaign(&campaign, 5), None);
+            let mut ui = HeadlessUI::new(Box::new(CautiousHunter::new(5)), TURNS);
+            driver.run(&mut ui);
+            assert_eq!(ui.get_turns(), TURNS, "{}", path.display());
+            assert_eq!(driver.game().get_replay().verify(), Ok(()), "{}", path.display());
+        }
+    }
+}
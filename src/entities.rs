@@ -1,8 +1,10 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, fmt::Display};
 
+use rand::Rng;
+use serde::{Deserialize, Serialize};
 use wasm_bindgen::prelude::*;
 
-use crate::map::{HexCell, HexCoord, HexDir};
+use crate::{clock::TimeOfDay, event::GameEvent, map::{HexCell, HexCoord, HexDir}, shop::{Equipment, Upgrade}, terrain::{Terrain, TerrainMap}, weather::WeatherKind};
 
 pub trait Damageable {
     fn take_damage(&mut self, amount: i32);
@@ -14,9 +16,51 @@ pub trait Attacker<T: Damageable> {
     fn attack(&self, target: &mut T);
 }
 
+/// Why the fisherman refused an action. A refused action doesn't take a turn.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ActionError {
+    /// only moving is possible in the harbor
+    InHarbor,
+    /// the target cell is `distance` cells away, farther than the `max` reach of the action
+    OutOfRange { distance: i32, max: i32 },
+    /// there is no `index`-th shark at `coord`
+    NoSuchShark { coord: HexCoord, index: usize },
+    /// the boat can only move to an adjacent cell
+    NotAdjacent,
+    /// the boat can't sail into this terrain
+    Blocked { terrain: Terrain },
+    /// the shop is in the harbor
+    NotInHarbor,
+    /// levels played on their own, e.g. by the simulator, have no shop
+    NoShop,
+    NotEnoughCoins { price: usize, coins: usize },
+    FullyUpgraded { upgrade: Upgrade },
+    /// the `index`-th upgrade doesn't exist
+    NoSuchUpgrade { index: usize },
+}
+
+impl Display for ActionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ActionError::InHarbor => write!(f, "This action is not allowed in harbor!"),
+            ActionError::OutOfRange { distance, max } => write!(f, "Target is {}NM away, but can only reach {}NM!", distance, max),
+            ActionError::NoSuchShark { coord, index } => write!(f, "There is no shark #{} at ({}, {}, {})!", index, coord.q, coord.r, coord.s),
+            ActionError::NotAdjacent => write!(f, "You can only move to an adjacent cell!"),
+            ActionError::Blocked { terrain } => write!(f, "You can't sail into {}!", terrain),
+            ActionError::NotInHarbor => write!(f, "The shop is in the harbor!"),
+            ActionError::NoShop => write!(f, "There is no shop here!"),
+            ActionError::NotEnoughCoins { price, coins } => write!(f, "That costs {} coins, but you only have {}!", price, coins),
+            ActionError::FullyUpgraded { upgrade } => write!(f, "Your {} is already fully upgraded!", upgrade),
+            ActionError::NoSuchUpgrade { index } => write!(f, "There is no upgrade #{}!", index),
+        }
+    }
+}
+
+impl std::error::Error for ActionError {}
+
 
 #[wasm_bindgen]
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub struct Marlin {
     discovered: bool, // Indicates whether this Marlin has been discovered
     hp: i32,
@@ -50,9 +94,15 @@ impl Marlin {
     }
 }
 
+impl Default for Marlin {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl PartialEq for Marlin {
     fn eq(&self, other: &Self) -> bool {
-        self as *const Marlin == other as *const Marlin
+        std::ptr::eq(self, other)
     }
 }
 impl Damageable for Marlin {
@@ -71,7 +121,7 @@ impl Damageable for Marlin {
 }
 
 #[wasm_bindgen]
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Shark {
     hp: i32,
 }
@@ -80,12 +130,21 @@ impl Shark {
     const ATTACK_POWER: i32 = 1;
     pub const MOVE_RADIUS: i32 = 1;
     pub const VISUAL_RADIUS: i32 = 2;
+    // sharks hunt boldly in the dark
+    const NIGHT_VISUAL_RADIUS: i32 = 3;
     pub const SMELL_RADIUS: i32 = 3;
     pub const fn new() -> Self {
         Shark {
             hp: Self::INITIAL_HP
         }
     }
+    /// How far a shark spots the fisherman from.
+    pub fn visual_radius(time: TimeOfDay) -> i32 {
+        match time {
+            TimeOfDay::Day => Self::VISUAL_RADIUS,
+            TimeOfDay::Night => Self::NIGHT_VISUAL_RADIUS,
+        }
+    }
 }
 #[wasm_bindgen]
 impl Shark {
@@ -100,6 +159,12 @@ impl Shark {
     }
 }
 
+impl Default for Shark {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Damageable for Shark {
     fn take_damage(&mut self, amount: i32) {
         self.hp -= amount;
@@ -122,77 +187,158 @@ impl <T: Damageable> Attacker<T> for Shark {
 }
 
 #[wasm_bindgen]
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Fisherman {
     coordinate: HexCoord,
     hp: i32,
     initial_hp: i32,
     attack_power: i32,
     captured_marlins: usize,
+    // captured marlins that had never been hurt, for scoring
+    #[serde(default)]
+    undamaged_captures: usize,
     capture_success_rate: f32,
+    // bought in the harbor shop, on top of the stats of the level
+    #[serde(default)]
+    equipment: Equipment,
 }
 impl Fisherman {
-    pub const HARBOR_COORD: HexCoord = HexCoord::ZERO;
-    const MOVE_RADIUS: i32 = 1;
-    const CAPTURE_RADIUS: i32 = 1;
+    pub const MOVE_RADIUS: i32 = 1;
+    pub const CAPTURE_RADIUS: i32 = 1;
     const DISCOVER_RADIUS: i32 = 2;
+    const NIGHT_DISCOVER_RADIUS: i32 = 1;
+    /// how far the fisherman sees by day, nothing is ever shown farther than this
     pub const VISUAL_RADIUS: i32 = 4;
+    const NIGHT_VISUAL_RADIUS: i32 = 2;
+    const FOG_VISUAL_RADIUS: i32 = 3;
+    const FOG_DISCOVER_RADIUS: i32 = 1;
+    // a storm halves the odds of a catch
+    const STORM_CAPTURE_FACTOR: f32 = 0.5;
     const CAPTURE_FAIL_DAMAGE: i32 = 1;
-    pub fn new(initial_hp: i32, attack_power: i32, capture_success_rate: f32) -> Self {
+    pub fn new(harbor: HexCoord, initial_hp: i32, attack_power: i32, capture_success_rate: f32) -> Self {
         Self {
-            coordinate: Self::HARBOR_COORD,
+            coordinate: harbor,
             hp: initial_hp,
             initial_hp,
             attack_power,
             captured_marlins: 0,
+            undamaged_captures: 0,
             capture_success_rate,
+            equipment: Equipment::default(),
         }
     }
-    pub fn operate(&mut self, dir: HexDir) -> bool {
+    /// Swaps in new equipment, better hull plating repairs the boat by as much as it adds.
+    pub fn equip(&mut self, equipment: Equipment) {
+        self.hp += equipment.hp_bonus() - self.equipment.hp_bonus();
+        self.equipment = equipment;
+    }
+    #[inline]
+    pub fn equipment(&self) -> &Equipment {
+        &self.equipment
+    }
+    pub fn attack_power(&self) -> i32 {
+        self.attack_power + self.equipment.attack_bonus()
+    }
+    pub fn operate(&mut self, dir: HexDir, terrain: &TerrainMap) -> Result<(), ActionError> {
         let new_coord = self.coordinate + dir;
         if self.coordinate.distance(&new_coord) > Self::MOVE_RADIUS {
-            return false
+            return Err(ActionError::NotAdjacent)
+        }
+        let target_terrain = terrain.at(&new_coord);
+        if !target_terrain.boat_can_enter() {
+            return Err(ActionError::Blocked { terrain: target_terrain });
+        }
+        self.coordinate = new_coord;
+        Ok(())
+    }
+    /// Lets a current carry the boat one cell, returns where it ended up if it moved.
+    pub fn drift(&mut self, terrain: &TerrainMap) -> Option<HexCoord> {
+        let Terrain::Current(dir) = terrain.at(&self.coordinate) else {
+            return None;
+        };
+        self.push(dir, terrain)
+    }
+    /// Pushes the boat one cell unless something is in the way, returns where it ended up if it moved.
+    pub fn push(&mut self, dir: HexDir, terrain: &TerrainMap) -> Option<HexCoord> {
+        let new_coord = self.coordinate + dir;
+        if !terrain.at(&new_coord).boat_can_enter() {
+            return None;
         }
         self.coordinate = new_coord;
-        true
+        Some(new_coord)
     }
-    pub fn discover_marlins(&self, grid: &mut HashMap<HexCoord, HexCell>) -> bool {
-        if self.coordinate == Self::HARBOR_COORD {
-            return false;
+    /// How far the fisherman sees, the darkest of night and fog wins.
+    pub fn visual_radius(&self, time: TimeOfDay, weather: WeatherKind) -> i32 {
+        let radius = match time {
+            TimeOfDay::Day => Self::VISUAL_RADIUS,
+            TimeOfDay::Night => Self::NIGHT_VISUAL_RADIUS,
+        };
+        match weather {
+            WeatherKind::Fog => radius.min(Self::FOG_VISUAL_RADIUS),
+            _ => radius,
         }
-        for coord in self.coordinate.within_radius(Self::DISCOVER_RADIUS) {
+    }
+    pub fn discover_radius(&self, time: TimeOfDay, weather: WeatherKind) -> i32 {
+        let radius = match time {
+            TimeOfDay::Day => Self::DISCOVER_RADIUS,
+            TimeOfDay::Night => Self::NIGHT_DISCOVER_RADIUS,
+        } + self.equipment.discover_bonus();
+        match weather {
+            WeatherKind::Fog => radius.min(Self::FOG_DISCOVER_RADIUS + self.equipment.discover_bonus()),
+            _ => radius,
+        }
+    }
+    /// Odds of catching a discovered marlin.
+    pub fn capture_success_rate(&self, weather: WeatherKind) -> f32 {
+        let rate = (self.capture_success_rate + self.equipment.capture_bonus()).min(1.0);
+        match weather {
+            WeatherKind::Storm => rate * Self::STORM_CAPTURE_FACTOR,
+            _ => rate,
+        }
+    }
+    pub fn discover_marlins(&self, grid: &mut HashMap<HexCoord, HexCell>, terrain: &TerrainMap, time: TimeOfDay, weather: WeatherKind) -> Result<(), ActionError> {
+        if terrain.is_harbor(&self.coordinate) {
+            return Err(ActionError::InHarbor);
+        }
+        for coord in self.coordinate.within_radius(self.discover_radius(time, weather)) {
+            if terrain.at(&coord).hides_marlins() {
+                continue;
+            }
             if let Some(cell) = grid.get_mut(&coord) {
                 cell.marlins.iter_mut().for_each(|m| m.discovered = true);
             }
         }
-        true
+        Ok(())
     }
     /// Method to capture Marlins in a selected cell
-    /// return if input is correct.
-    pub fn capture_marlins(&mut self, coord: HexCoord, grid: &mut HashMap<HexCoord, HexCell>) -> bool {
-        if self.coordinate == Self::HARBOR_COORD {
-            return false;
+    pub fn capture_marlins(&mut self, coord: HexCoord, grid: &mut HashMap<HexCoord, HexCell>, terrain: &TerrainMap, weather: WeatherKind, rng: &mut impl Rng, events: &mut Vec<GameEvent>) -> Result<(), ActionError> {
+        if terrain.is_harbor(&self.coordinate) {
+            return Err(ActionError::InHarbor);
         }
         // Check if the target coordinate is within the capture radius
-
-        if self.coordinate.distance(&coord) > Self::CAPTURE_RADIUS {
-            return false;
+        let distance = self.coordinate.distance(&coord);
+        if distance > Self::CAPTURE_RADIUS {
+            return Err(ActionError::OutOfRange { distance, max: Self::CAPTURE_RADIUS });
         }
 
         // Check if there are discovered Marlins in the selected cell
         let Some(cell) = grid.get_mut(&coord) else {
-            return true;
+            return Ok(());
         };
+        let mut undamaged_captures = 0;
         let new_marlins = cell.marlins.iter().filter_map(|s| {
             if !s.discovered {
-                return Some(s.clone()) // keep all
+                return Some(*s) // keep all
             }
-            if self.attempt_capture() {
+            if self.attempt_capture(weather, rng) {
                 // success, remove marlin
+                undamaged_captures += !s.is_hurt() as usize;
+                events.push(GameEvent::MarlinCaptured { coord });
                 None
             } else {
-                let mut new_marlin = s.clone();
+                let mut new_marlin = *s;
                 new_marlin.take_damage(Self::CAPTURE_FAIL_DAMAGE);
+                events.push(GameEvent::CaptureFailed { coord, hp_left: new_marlin.hp });
                 Some(new_marlin)
             }
         }).collect::<Vec<_>>();
@@ -203,24 +349,26 @@ impl Fisherman {
 
         // add to captured_marlins
         self.captured_marlins += capture_num;
-        true
+        self.undamaged_captures += undamaged_captures;
+        Ok(())
     }
 
     // Method to attempt to capture a Marlin based on success rate
-    fn attempt_capture(&self) -> bool {
+    fn attempt_capture(&self, weather: WeatherKind, rng: &mut impl Rng) -> bool {
         // Simulate capture based on success rate
-        let success_chance = rand::random::<f32>(); // Random number between 0.0 and 1.0
-        success_chance < self.capture_success_rate
+        let success_chance = rng.gen::<f32>(); // Random number between 0.0 and 1.0
+        success_chance < self.capture_success_rate(weather)
     }
-    pub fn attack_shark(&self, shark: Option<&mut Shark>) -> bool {
-        if self.coordinate == Self::HARBOR_COORD {
-            return false;
+    pub fn attack_shark(&self, coord: HexCoord, index: usize, grid: &mut HashMap<HexCoord, HexCell>, terrain: &TerrainMap, events: &mut Vec<GameEvent>) -> Result<(), ActionError> {
+        if terrain.is_harbor(&self.coordinate) {
+            return Err(ActionError::InHarbor);
         }
-        let Some(shark) = shark else {
-            return false;
+        let Some(shark) = grid.get_mut(&coord).and_then(|c| c.sharks.get_mut(index)) else {
+            return Err(ActionError::NoSuchShark { coord, index });
         };
         self.attack(shark);
-        true
+        events.push(GameEvent::SharkHit { coord, hp_left: shark.hp });
+        Ok(())
     }
 }
 #[wasm_bindgen]
@@ -233,6 +381,10 @@ impl Fisherman {
     pub fn get_captured_marlins(&self) -> usize {
         self.captured_marlins
     }
+    #[inline]
+    pub fn get_undamaged_captures(&self) -> usize {
+        self.undamaged_captures
+    }
 
     #[inline]
     pub fn get_hp(&self) -> i32 {
@@ -240,7 +392,8 @@ impl Fisherman {
     }
     #[inline]
     pub fn get_initial_hp(&self) -> i32 {
-        self.initial_hp
+        // hull plating included
+        self.initial_hp + self.equipment.hp_bonus()
     }
 }
 
@@ -254,13 +407,13 @@ impl Damageable for Fisherman {
     }
     
     fn is_hurt(&self) -> bool {
-        self.hp < self.initial_hp
+        self.hp < self.get_initial_hp()
     }
 }
 
 // Implement the Attacker trait for Fisherman targeting Sharks
 impl Attacker<Shark> for Fisherman {
     fn attack(&self, target: &mut Shark) {
-        target.take_damage(self.attack_power);
+        target.take_damage(self.attack_power());
     }
 }
\ No newline at end of file
@@ -0,0 +1,143 @@
+use std::fmt::Display;
+
+use wasm_bindgen::prelude::*;
+
+use crate::{map::HexCoord, weather::WeatherKind};
+
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Creature {
+    #[default]
+    Marlin,
+    Shark,
+}
+
+/// Something that happened during a turn, in the order it happened.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameEvent {
+    MarlinCaptured { coord: HexCoord },
+    /// the marlin got away hurt
+    CaptureFailed { coord: HexCoord, hp_left: i32 },
+    /// the fisherman harpooned a shark
+    SharkHit { coord: HexCoord, hp_left: i32 },
+    SharkAttackedPlayer { coord: HexCoord, hp_left: i32 },
+    SharkAttackedMarlin { coord: HexCoord, hp_left: i32 },
+    SharkKilled { coord: HexCoord },
+    MarlinDied { coord: HexCoord },
+    CreatureSpawned { coord: HexCoord, creature: Creature },
+    /// the creature went out of sight and was removed
+    CreatureDespawned { coord: HexCoord, creature: Creature },
+    MarlinMoved { from: HexCoord, to: HexCoord },
+    SharkMoved { from: HexCoord, to: HexCoord },
+    /// a current carried the boat
+    BoatDrifted { from: HexCoord, to: HexCoord },
+    /// a storm gust pushed the boat
+    BoatBlown { from: HexCoord, to: HexCoord },
+    WeatherChanged { weather: WeatherKind },
+    NightFell,
+    DayBroke,
+    /// the turn limit ran out before the boat made it back
+    OutOfTime,
+}
+
+impl GameEvent {
+    /// Whether the event is worth a line in the turn log; movements and (de)spawns happen every turn.
+    pub fn is_notable(&self) -> bool {
+        !matches!(self, GameEvent::CreatureSpawned { .. } | GameEvent::CreatureDespawned { .. } | GameEvent::MarlinMoved { .. } | GameEvent::SharkMoved { .. })
+    }
+}
+
+impl Display for GameEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GameEvent::MarlinCaptured { .. } => write!(f, "Captured a marlin!"),
+            GameEvent::CaptureFailed { hp_left, .. } if *hp_left <= 0 => write!(f, "A marlin was killed by the net."),
+            GameEvent::CaptureFailed { hp_left, .. } => write!(f, "A marlin escaped the net, HP left: {}.", hp_left),
+            GameEvent::SharkHit { hp_left, .. } => write!(f, "You hit a shark, HP left: {}.", hp_left),
+            GameEvent::SharkAttackedPlayer { hp_left, .. } => write!(f, "A shark bit you! HP left: {}.", hp_left),
+            GameEvent::SharkAttackedMarlin { hp_left, .. } => write!(f, "A shark bit a marlin, HP left: {}.", hp_left),
+            GameEvent::SharkKilled { .. } => write!(f, "A shark was killed."),
+            GameEvent::MarlinDied { .. } => write!(f, "A marlin died."),
+            GameEvent::CreatureSpawned { creature, .. } => write!(f, "A {:?} appeared.", creature),
+            GameEvent::CreatureDespawned { creature, .. } => write!(f, "A {:?} went out of sight.", creature),
+            GameEvent::MarlinMoved { .. } => write!(f, "A marlin swam."),
+            GameEvent::SharkMoved { .. } => write!(f, "A shark swam."),
+            GameEvent::BoatDrifted { from, to } => write!(f, "The current carried you {}.", *to - *from),
+            GameEvent::BoatBlown { from, to } => write!(f, "A gust blew you {}.", *to - *from),
+            GameEvent::WeatherChanged { weather: WeatherKind::Calm } => write!(f, "The sea calms down."),
+            GameEvent::WeatherChanged { weather: WeatherKind::Wind } => write!(f, "The wind picks up."),
+            GameEvent::WeatherChanged { weather: WeatherKind::Fog } => write!(f, "Fog rolls in."),
+            GameEvent::WeatherChanged { weather: WeatherKind::Storm } => write!(f, "A storm is coming!"),
+            GameEvent::NightFell => write!(f, "Night falls, sharks grow bolder."),
+            GameEvent::DayBroke => write!(f, "Day breaks."),
+            GameEvent::OutOfTime => write!(f, "Out of time! You didn't make it back to harbor."),
+        }
+    }
+}
+
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WasmGameEventType {
+    MarlinCaptured,
+    CaptureFailed,
+    SharkHit,
+    SharkAttackedPlayer,
+    SharkAttackedMarlin,
+    SharkKilled,
+    MarlinDied,
+    CreatureSpawned,
+    CreatureDespawned,
+    MarlinMoved,
+    SharkMoved,
+    BoatDrifted,
+    BoatBlown,
+    WeatherChanged,
+    NightFell,
+    DayBroke,
+    OutOfTime,
+}
+
+/// `GameEvent` flattened for JS: `coord` is where it happened (or `from` for moves, zero for events happening everywhere),
+/// `to` is the destination of moves, `hp_left` is set for attacks and failed captures, `weather` for weather changes.
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy)]
+pub struct WasmGameEvent {
+    pub event_type: WasmGameEventType,
+    pub coord: HexCoord,
+    pub to: HexCoord,
+    pub hp_left: i32,
+    pub creature: Creature,
+    pub weather: WeatherKind,
+}
+
+impl From<GameEvent> for WasmGameEvent {
+    fn from(value: GameEvent) -> Self {
+        let event = |event_type, coord| Self {
+            event_type,
+            coord,
+            to: coord,
+            hp_left: 0,
+            creature: Creature::default(),
+            weather: WeatherKind::default(),
+        };
+        match value {
+            GameEvent::MarlinCaptured { coord } => event(WasmGameEventType::MarlinCaptured, coord),
+            GameEvent::CaptureFailed { coord, hp_left } => Self { hp_left, ..event(WasmGameEventType::CaptureFailed, coord) },
+            GameEvent::SharkHit { coord, hp_left } => Self { hp_left, ..event(WasmGameEventType::SharkHit, coord) },
+            GameEvent::SharkAttackedPlayer { coord, hp_left } => Self { hp_left, ..event(WasmGameEventType::SharkAttackedPlayer, coord) },
+            GameEvent::SharkAttackedMarlin { coord, hp_left } => Self { hp_left, ..event(WasmGameEventType::SharkAttackedMarlin, coord) },
+            GameEvent::SharkKilled { coord } => event(WasmGameEventType::SharkKilled, coord),
+            GameEvent::MarlinDied { coord } => event(WasmGameEventType::MarlinDied, coord),
+            GameEvent::CreatureSpawned { coord, creature } => Self { creature, ..event(WasmGameEventType::CreatureSpawned, coord) },
+            GameEvent::CreatureDespawned { coord, creature } => Self { creature, ..event(WasmGameEventType::CreatureDespawned, coord) },
+            GameEvent::MarlinMoved { from, to } => Self { to, ..event(WasmGameEventType::MarlinMoved, from) },
+            GameEvent::SharkMoved { from, to } => Self { to, ..event(WasmGameEventType::SharkMoved, from) },
+            GameEvent::BoatDrifted { from, to } => Self { to, ..event(WasmGameEventType::BoatDrifted, from) },
+            GameEvent::BoatBlown { from, to } => Self { to, ..event(WasmGameEventType::BoatBlown, from) },
+            GameEvent::WeatherChanged { weather } => Self { weather, ..event(WasmGameEventType::WeatherChanged, HexCoord::ZERO) },
+            GameEvent::NightFell => event(WasmGameEventType::NightFell, HexCoord::ZERO),
+            GameEvent::DayBroke => event(WasmGameEventType::DayBroke, HexCoord::ZERO),
+            GameEvent::OutOfTime => event(WasmGameEventType::OutOfTime, HexCoord::ZERO),
+        }
+    }
+}
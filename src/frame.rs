@@ -0,0 +1,166 @@
+use std::fmt::{Display, Write};
+
+/// The terminal's basic colors, `Default` leaves the terminal's own.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Color {
+    #[default]
+    Default,
+    Black,
+    Red,
+    Green,
+    Yellow,
+    Blue,
+    Magenta,
+    Cyan,
+    White,
+    Gray,
+    // dim shades, so text stays readable on top of them as a background
+    DarkRed,
+    DarkGreen,
+    DarkBlue,
+    DarkGray,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Style {
+    pub fg: Color,
+    pub bg: Color,
+    pub bold: bool,
+    pub italic: bool,
+}
+
+impl Style {
+    pub const PLAIN: Style = Style { fg: Color::Default, bg: Color::Default, bold: false, italic: false };
+
+    pub fn fg(color: Color) -> Self {
+        Self { fg: color, ..Self::PLAIN }
+    }
+
+    pub fn bg(color: Color) -> Self {
+        Self { bg: color, ..Self::PLAIN }
+    }
+
+    /// The same style drawn on `color`.
+    pub fn on(self, color: Color) -> Self {
+        Self { bg: color, ..self }
+    }
+
+    pub fn bold(self) -> Self {
+        Self { bold: true, ..self }
+    }
+
+    pub fn italic(self) -> Self {
+        Self { italic: true, ..self }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cell {
+    pub ch: char,
+    pub style: Style,
+}
+
+/// A screen drawn in memory: lines of styled characters, written to like a terminal with `write!`.
+/// Front-ends decide how to show it, its `Display` is the plain text.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Frame {
+    lines: Vec<Vec<Cell>>,
+    style: Style,
+    monochrome: bool,
+}
+
+impl Frame {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A frame for terminals without colors: colors are dropped as they are written, bold and italic are kept.
+    pub fn monochrome() -> Self {
+        Self { monochrome: true, ..Self::default() }
+    }
+
+    /// Whether colors are dropped, drawings should then mark with characters what they would color.
+    pub fn is_monochrome(&self) -> bool {
+        self.monochrome
+    }
+
+    /// The style of everything written from now on.
+    pub fn set_style(&mut self, style: Style) {
+        self.style = style;
+    }
+
+    pub fn reset_style(&mut self) {
+        self.style = Style::PLAIN;
+    }
+
+    /// Writes `text` in `style`, then goes back to the current style.
+    pub fn styled(&mut self, style: Style, text: impl Display) -> std::fmt::Result {
+        let current = self.style;
+        self.style = style;
+        let res = write!(self, "{}", text);
+        self.style = current;
+        res
+    }
+
+    pub fn lines(&self) -> &[Vec<Cell>] {
+        &self.lines
+    }
+
+    /// The text of line `row`, without styles.
+    pub fn line_text(&self, row: usize) -> String {
+        self.lines.get(row).map_or(String::new(), |line| line.iter().map(|c| c.ch).collect())
+    }
+}
+
+impl Write for Frame {
+    fn write_str(&mut self, s: &str) -> std::fmt::Result {
+        let style = if self.monochrome { Style { fg: Color::Default, bg: Color::Default, ..self.style } } else { self.style };
+        for ch in s.chars() {
+            if self.lines.is_empty() {
+                self.lines.push(vec![]);
+            }
+            match ch {
+                '\n' => self.lines.push(vec![]),
+                '\r' => {},
+                _ => self.lines.last_mut().unwrap().push(Cell { ch, style }),
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Display for Frame {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // trailing blanks are only padding, and get in the way of comparing boards
+        for row in 0..self.lines.len() {
+            let text = self.line_text(row);
+            // a final "\n" only starts a line
+            if row + 1 == self.lines.len() && text.is_empty() {
+                break;
+            }
+            writeln!(f, "{}", text.trim_end())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display_trims_padding() {
+        let mut frame = Frame::new();
+        write!(frame, "  /  \\   \n\\__/  \n").unwrap();
+        assert_eq!(frame.lines().len(), 3);
+        assert_eq!(frame.line_text(0), "  /  \\   ");
+        assert_eq!(frame.to_string(), "  /  \\\n\\__/\n");
+    }
+
+    #[test]
+    fn display_keeps_an_unfinished_line() {
+        let mut frame = Frame::new();
+        write!(frame, "map\n\nOperation: ").unwrap();
+        assert_eq!(frame.to_string(), "map\n\nOperation:\n");
+    }
+}
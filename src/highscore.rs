@@ -0,0 +1,161 @@
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use crate::{level::Game, score::LevelScore};
+
+/// One finished run, as kept in the high-score table.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RunRecord {
+    pub name: String,
+    /// seconds since the Unix epoch, passed in by the front-end since wasm has no clock
+    pub timestamp: u64,
+    pub seed: u64,
+    pub score: usize,
+    pub levels_reached: usize,
+    pub won: bool,
+    pub levels: Vec<LevelScore>,
+}
+
+impl RunRecord {
+    pub fn new(game: &Game, name: String, timestamp: u64) -> Self {
+        let levels = game.scores().to_vec();
+        Self {
+            name,
+            timestamp,
+            seed: game.get_seed(),
+            score: game.get_score(),
+            levels_reached: game.get_furthest_level() + 1,
+            won: levels.len() == game.get_level_count(),
+            levels,
+        }
+    }
+}
+
+impl Display for RunRecord {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:<16} {:>7}  level {:>2}{}  {}  seed {}", self.name, self.score, self.levels_reached, if self.won { " (won)" } else { "      " }, format_date(self.timestamp), self.seed)
+    }
+}
+
+// "YYYY-MM-DD" in UTC, see http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+fn format_date(timestamp: u64) -> String {
+    let z = (timestamp / 86400) as i64 + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + (month <= 2) as i64;
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// Every run played on this machine. Kept in a file by the CLI and in localStorage by the web build.
+#[wasm_bindgen]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HighScores {
+    version: u32,
+    runs: Vec<RunRecord>,
+}
+
+impl HighScores {
+    const VERSION: u32 = 1;
+
+    pub fn add(&mut self, run: RunRecord) {
+        self.runs.push(run);
+    }
+
+    /// Run history, oldest first.
+    pub fn runs(&self) -> &[RunRecord] {
+        &self.runs
+    }
+
+    /// The `n` best runs, the earlier one first on a tie.
+    pub fn top(&self, n: usize) -> Vec<&RunRecord> {
+        let mut runs = self.runs.iter().collect::<Vec<_>>();
+        // stable, so ties keep the history order
+        runs.sort_by_key(|run| std::cmp::Reverse(run.score));
+        runs.truncate(n);
+        runs
+    }
+}
+
+impl Default for HighScores {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[wasm_bindgen]
+impl HighScores {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Self {
+        Self {
+            version: Self::VERSION,
+            runs: vec![],
+        }
+    }
+
+    pub fn from_json(json: &str) -> Result<HighScores, String> {
+        let scores: HighScores = serde_json::from_str(json).map_err(|e| format!("malformed high scores: {}", e))?;
+        if scores.version != Self::VERSION {
+            return Err(format!("unsupported high score version {}, expected {}", scores.version, Self::VERSION));
+        }
+        Ok(scores)
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+
+    pub fn add_game(&mut self, game: &Game, name: String, timestamp: u64) {
+        self.add(RunRecord::new(game, name, timestamp));
+    }
+
+    #[inline]
+    pub fn get_run_count(&self) -> usize {
+        self.runs.len()
+    }
+
+    /// One line per run of the top `n`, best first, for displaying.
+    pub fn get_top_lines(&self, n: usize) -> Vec<String> {
+        self.top(n).into_iter().enumerate().map(|(i, run)| format!("{:>2}. {}", i + 1, run)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(name: &str, score: usize) -> RunRecord {
+        RunRecord { name: name.to_string(), timestamp: 0, seed: 0, score, levels_reached: 1, won: false, levels: vec![] }
+    }
+
+    #[test]
+    fn dates_are_civil_days() {
+        assert_eq!(format_date(0), "1970-01-01");
+        assert_eq!(format_date(86399), "1970-01-01");
+        // 2024-02-29 and the day after
+        assert_eq!(format_date(1709164800), "2024-02-29");
+        assert_eq!(format_date(1709164800 + 86400), "2024-03-01");
+        assert_eq!(format_date(951782400), "2000-02-29");
+    }
+
+    #[test]
+    fn top_is_best_first_and_truncated() {
+        let mut scores = HighScores::new();
+        for (name, score) in [("a", 30), ("b", 50), ("c", 10), ("d", 50), ("e", 40)] {
+            scores.add(run(name, score));
+        }
+        let names = |n| scores.top(n).iter().map(|r| r.name.as_str()).collect::<Vec<_>>();
+        // the earlier of two equal runs first
+        assert_eq!(names(10), ["b", "d", "e", "a", "c"]);
+        assert_eq!(names(3), ["b", "d", "e"]);
+        assert!(names(0).is_empty());
+        assert_eq!(scores.get_top_lines(2).len(), 2);
+        assert!(scores.get_top_lines(2)[1].starts_with(" 2. d "));
+    }
+}
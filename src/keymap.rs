@@ -0,0 +1,405 @@
+use std::collections::BTreeMap;
+
+use serde::Deserialize;
+use termion::event::Key;
+
+use crate::{level::UserAction, map::HexDir};
+
+/// Something a single key press does in the CLI.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KeyAction {
+    MoveUp,
+    MoveDown,
+    MoveUpleft,
+    MoveDownleft,
+    MoveUpright,
+    MoveDownright,
+    Stay,
+    CaptureUp,
+    CaptureDown,
+    CaptureUpleft,
+    CaptureDownleft,
+    CaptureUpright,
+    CaptureDownright,
+    CaptureHere,
+    Discover,
+    Attack,
+    Hint,
+    Autopilot,
+    Shop,
+    Help,
+    /// starts a line for the longer commands like `save` or `level N`
+    Command,
+    Quit,
+}
+
+impl KeyAction {
+    pub const ALL: [KeyAction; 22] = [
+        KeyAction::MoveUp, KeyAction::MoveDown, KeyAction::MoveUpleft, KeyAction::MoveDownleft, KeyAction::MoveUpright, KeyAction::MoveDownright, KeyAction::Stay,
+        KeyAction::CaptureUp, KeyAction::CaptureDown, KeyAction::CaptureUpleft, KeyAction::CaptureDownleft, KeyAction::CaptureUpright, KeyAction::CaptureDownright, KeyAction::CaptureHere,
+        KeyAction::Discover, KeyAction::Attack, KeyAction::Hint, KeyAction::Autopilot, KeyAction::Shop, KeyAction::Help, KeyAction::Command, KeyAction::Quit,
+    ];
+
+    /// The name used in keymap files.
+    pub fn name(&self) -> &'static str {
+        match self {
+            KeyAction::MoveUp => "move_up",
+            KeyAction::MoveDown => "move_down",
+            KeyAction::MoveUpleft => "move_upleft",
+            KeyAction::MoveDownleft => "move_downleft",
+            KeyAction::MoveUpright => "move_upright",
+            KeyAction::MoveDownright => "move_downright",
+            KeyAction::Stay => "stay",
+            KeyAction::CaptureUp => "capture_up",
+            KeyAction::CaptureDown => "capture_down",
+            KeyAction::CaptureUpleft => "capture_upleft",
+            KeyAction::CaptureDownleft => "capture_downleft",
+            KeyAction::CaptureUpright => "capture_upright",
+            KeyAction::CaptureDownright => "capture_downright",
+            KeyAction::CaptureHere => "capture_here",
+            KeyAction::Discover => "discover",
+            KeyAction::Attack => "attack",
+            KeyAction::Hint => "hint",
+            KeyAction::Autopilot => "autopilot",
+            KeyAction::Shop => "shop",
+            KeyAction::Help => "help",
+            KeyAction::Command => "command",
+            KeyAction::Quit => "quit",
+        }
+    }
+
+    /// What the help screen calls it.
+    pub fn description(&self) -> &'static str {
+        match self {
+            KeyAction::MoveUp => "Move Up",
+            KeyAction::MoveDown => "Move Down",
+            KeyAction::MoveUpleft => "Move Upleft",
+            KeyAction::MoveDownleft => "Move Downleft",
+            KeyAction::MoveUpright => "Move Upright",
+            KeyAction::MoveDownright => "Move Downright",
+            KeyAction::Stay => "Stay In Place",
+            KeyAction::CaptureUp => "Capture Up",
+            KeyAction::CaptureDown => "Capture Down",
+            KeyAction::CaptureUpleft => "Capture Upleft",
+            KeyAction::CaptureDownleft => "Capture Downleft",
+            KeyAction::CaptureUpright => "Capture Upright",
+            KeyAction::CaptureDownright => "Capture Downright",
+            KeyAction::CaptureHere => "Capture Current",
+            KeyAction::Discover => "Find Nearby Marlins",
+            KeyAction::Attack => "Attack Nearby Sharks",
+            KeyAction::Hint => "Hint",
+            KeyAction::Autopilot => "Autopilot One Turn",
+            KeyAction::Shop => "Shop (In Harbor)",
+            KeyAction::Help => "Show Key Binds",
+            KeyAction::Command => "Type A Command",
+            KeyAction::Quit => "Quit",
+        }
+    }
+
+    /// The game action this key stands for, if it is one.
+    pub fn user_action(&self) -> Option<UserAction> {
+        let action = match self {
+            KeyAction::MoveUp => UserAction::Move(HexDir::NORTH),
+            KeyAction::MoveDown => UserAction::Move(HexDir::SOUTH),
+            KeyAction::MoveUpleft => UserAction::Move(HexDir::NORTHWEST),
+            KeyAction::MoveDownleft => UserAction::Move(HexDir::SOUTHWEST),
+            KeyAction::MoveUpright => UserAction::Move(HexDir::NORTHEAST),
+            KeyAction::MoveDownright => UserAction::Move(HexDir::SOUTHEAST),
+            KeyAction::Stay => UserAction::Move(HexDir::ZERO),
+            KeyAction::CaptureUp => UserAction::Capture(HexDir::NORTH),
+            KeyAction::CaptureDown => UserAction::Capture(HexDir::SOUTH),
+            KeyAction::CaptureUpleft => UserAction::Capture(HexDir::NORTHWEST),
+            KeyAction::CaptureDownleft => UserAction::Capture(HexDir::SOUTHWEST),
+            KeyAction::CaptureUpright => UserAction::Capture(HexDir::NORTHEAST),
+            KeyAction::CaptureDownright => UserAction::Capture(HexDir::SOUTHEAST),
+            KeyAction::CaptureHere => UserAction::Capture(HexDir::ZERO),
+            KeyAction::Discover => UserAction::Discover,
+            _ => return None,
+        };
+        Some(action)
+    }
+}
+
+type Keys = &'static [(KeyAction, &'static str)];
+
+/// The built-in key sets a keymap file can start from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Layout {
+    /// W/E/C/X/Z/Q around S, Shift to capture
+    #[default]
+    Qwerty,
+    /// the same keys where they sit on a French keyboard
+    Azerty,
+    /// roguelike Y/K/U/N/J/B around `.`, Shift to capture, `,` to capture in place
+    Vi,
+    /// the digits around 5, Alt to capture
+    Numpad,
+}
+
+impl Layout {
+    fn bindings(&self) -> Vec<(KeyAction, Vec<&'static str>)> {
+        use KeyAction::*;
+        // keys every layout shares
+        let arrows = [(MoveUp, "up"), (MoveDown, "down"), (MoveUpleft, "home"), (MoveDownleft, "end"), (MoveUpright, "pageup"), (MoveDownright, "pagedown"), (CaptureUp, "shift+up"), (CaptureDown, "shift+down")];
+        let digits = [(MoveUp, "8"), (MoveDown, "2"), (MoveUpleft, "7"), (MoveDownleft, "1"), (MoveUpright, "9"), (MoveDownright, "3"), (Stay, "5")];
+        let (letters, extra): (Keys, Keys) = match self {
+            Layout::Qwerty => (
+                &[(MoveUp, "w"), (MoveDown, "x"), (MoveUpleft, "q"), (MoveDownleft, "z"), (MoveUpright, "e"), (MoveDownright, "c"), (Stay, "s"),
+                  (CaptureUp, "W"), (CaptureDown, "X"), (CaptureUpleft, "Q"), (CaptureDownleft, "Z"), (CaptureUpright, "E"), (CaptureDownright, "C"), (CaptureHere, "S")],
+                &[(Discover, "f"), (Attack, "a"), (Hint, "h"), (Autopilot, "p"), (Shop, "b")],
+            ),
+            Layout::Azerty => (
+                &[(MoveUp, "z"), (MoveDown, "x"), (MoveUpleft, "a"), (MoveDownleft, "w"), (MoveUpright, "e"), (MoveDownright, "c"), (Stay, "s"),
+                  (CaptureUp, "Z"), (CaptureDown, "X"), (CaptureUpleft, "A"), (CaptureDownleft, "W"), (CaptureUpright, "E"), (CaptureDownright, "C"), (CaptureHere, "S")],
+                &[(Discover, "f"), (Attack, "q"), (Hint, "h"), (Autopilot, "p"), (Shop, "b")],
+            ),
+            Layout::Vi => (
+                &[(MoveUp, "k"), (MoveDown, "j"), (MoveUpleft, "y"), (MoveDownleft, "b"), (MoveUpright, "u"), (MoveDownright, "n"), (Stay, "."),
+                  (CaptureUp, "K"), (CaptureDown, "J"), (CaptureUpleft, "Y"), (CaptureDownleft, "B"), (CaptureUpright, "U"), (CaptureDownright, "N"), (CaptureHere, ",")],
+                &[(Discover, "f"), (Attack, "a"), (Hint, "h"), (Autopilot, "p"), (Shop, "m")],
+            ),
+            Layout::Numpad => (
+                &[(CaptureUp, "alt+8"), (CaptureDown, "alt+2"), (CaptureUpleft, "alt+7"), (CaptureDownleft, "alt+1"), (CaptureUpright, "alt+9"), (CaptureDownright, "alt+3"), (CaptureHere, "alt+5")],
+                &[(Discover, "0"), (Attack, "."), (Hint, "-"), (Autopilot, "+"), (Shop, "*")],
+            ),
+        };
+        let common = [(Help, "?"), (Command, ":"), (Quit, "ctrl+c"), (Quit, "ctrl+d")];
+        // the letters come first, the help screen lists keys in this order
+        let mut bindings = KeyAction::ALL.iter().map(|a| (*a, vec![])).collect::<Vec<_>>();
+        for (action, key) in letters.iter().chain(&digits).chain(&arrows).chain(extra).chain(&common) {
+            bindings.iter_mut().find(|(a, _)| a == action).unwrap().1.push(*key);
+        }
+        bindings
+    }
+}
+
+// the format of a keymap file: a layout, and the actions bound to other keys than the layout's
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct KeymapFile {
+    #[serde(default)]
+    layout: Layout,
+    #[serde(default)]
+    bindings: BTreeMap<KeyAction, Vec<String>>,
+}
+
+/// Which key does what in the CLI.
+#[derive(Debug, Clone)]
+pub struct Keymap {
+    bindings: Vec<(KeyAction, Vec<Key>)>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Self::with_layout(Layout::default())
+    }
+}
+
+impl Keymap {
+    pub fn with_layout(layout: Layout) -> Self {
+        let bindings = layout.bindings().into_iter()
+            .map(|(action, keys)| (action, keys.iter().map(|k| parse_key(k).unwrap()).collect()))
+            .collect();
+        Self { bindings }
+    }
+
+    /// Reads a keymap file, e.g. `{ "layout": "vi", "bindings": { "discover": ["space"] } }`.
+    /// Each action listed replaces all of the layout's keys for it.
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        let file: KeymapFile = serde_json::from_str(json).map_err(|e| format!("malformed keymap: {}", e))?;
+        let mut keymap = Self::with_layout(file.layout);
+        for (action, names) in file.bindings {
+            let keys = names.iter()
+                .map(|name| parse_key(name).ok_or_else(|| format!("invalid keymap: unknown key `{}` for `{}`", name, action.name())))
+                .collect::<Result<Vec<_>, _>>()?;
+            keymap.bindings.iter_mut().find(|(a, _)| *a == action).unwrap().1 = keys;
+        }
+        keymap.validate()?;
+        Ok(keymap)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        let mut seen = Vec::<(Key, KeyAction)>::new();
+        for (action, keys) in &self.bindings {
+            for key in keys {
+                if let Some((_, other)) = seen.iter().find(|(k, a)| k == key && a != action) {
+                    return Err(format!("invalid keymap: {} is bound to both `{}` and `{}`", key_label(*key), other.name(), action.name()));
+                }
+                seen.push((*key, *action));
+            }
+        }
+        if self.keys(KeyAction::Quit).is_empty() {
+            return Err("invalid keymap: `quit` needs at least one key".to_string());
+        }
+        Ok(())
+    }
+
+    pub fn action(&self, key: Key) -> Option<KeyAction> {
+        self.bindings.iter().find(|(_, keys)| keys.contains(&key)).map(|(action, _)| *action)
+    }
+
+    pub fn keys(&self, action: KeyAction) -> &[Key] {
+        self.bindings.iter().find(|(a, _)| *a == action).map_or(&[], |(_, keys)| keys)
+    }
+
+    /// The keys of `action` as the help screen shows them, e.g. "W, 8, ↑".
+    pub fn label(&self, action: KeyAction) -> String {
+        match self.keys(action) {
+            [] => "(unbound)".to_string(),
+            keys => keys.iter().map(|k| key_label(*k)).collect::<Vec<_>>().join(", "),
+        }
+    }
+}
+
+// "w", "W", "space", "pageup", "shift+up", "ctrl+c", "alt+8", "f1"...
+fn parse_key(name: &str) -> Option<Key> {
+    let mut chars = name.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return Some(Key::Char(c));
+    }
+    let lower = name.to_lowercase();
+    let single = |rest: &str| {
+        let mut chars = rest.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => Some(c),
+            _ => None,
+        }
+    };
+    if let Some(rest) = lower.strip_prefix("ctrl+") {
+        return single(rest).map(Key::Ctrl);
+    }
+    if let Some(rest) = name.get("alt+".len()..).filter(|_| lower.starts_with("alt+")) {
+        return single(rest).map(Key::Alt);
+    }
+    if let Some(n) = lower.strip_prefix('f').and_then(|n| n.parse::<u8>().ok()).filter(|n| (1..=12).contains(n)) {
+        return Some(Key::F(n));
+    }
+    let key = match lower.as_str() {
+        "space" => Key::Char(' '),
+        "enter" => Key::Char('\n'),
+        "tab" => Key::Char('\t'),
+        "backspace" => Key::Backspace,
+        "esc" => Key::Esc,
+        "up" => Key::Up,
+        "down" => Key::Down,
+        "left" => Key::Left,
+        "right" => Key::Right,
+        "shift+up" => Key::ShiftUp,
+        "shift+down" => Key::ShiftDown,
+        "shift+left" => Key::ShiftLeft,
+        "shift+right" => Key::ShiftRight,
+        "home" => Key::Home,
+        "end" => Key::End,
+        "pageup" => Key::PageUp,
+        "pagedown" => Key::PageDown,
+        "insert" => Key::Insert,
+        "delete" => Key::Delete,
+        _ => return None,
+    };
+    Some(key)
+}
+
+// how a key is printed on the help screen, letters as on the keycap
+pub(crate) fn key_label(key: Key) -> String {
+    match key {
+        Key::Char(' ') => "Space".to_string(),
+        Key::Char('\n') => "Enter".to_string(),
+        Key::Char('\t') => "Tab".to_string(),
+        Key::BackTab => "Shift + Tab".to_string(),
+        Key::Char(c) if c.is_lowercase() => c.to_uppercase().to_string(),
+        Key::Char(c) if c.is_uppercase() => format!("Shift + {}", c),
+        Key::Char(c) => c.to_string(),
+        Key::Ctrl(c) => format!("Ctrl + {}", c.to_ascii_uppercase()),
+        Key::Alt(c) => format!("Alt + {}", key_label(Key::Char(c))),
+        Key::F(n) => format!("F{}", n),
+        Key::Up => "↑".to_string(),
+        Key::Down => "↓".to_string(),
+        Key::Left => "←".to_string(),
+        Key::Right => "→".to_string(),
+        Key::ShiftUp => "Shift + ↑".to_string(),
+        Key::ShiftDown => "Shift + ↓".to_string(),
+        Key::ShiftLeft => "Shift + ←".to_string(),
+        Key::ShiftRight => "Shift + →".to_string(),
+        Key::Home => "Home".to_string(),
+        Key::End => "End".to_string(),
+        Key::PageUp => "PgUp".to_string(),
+        Key::PageDown => "PgDn".to_string(),
+        Key::Backspace => "Backspace".to_string(),
+        Key::Esc => "Esc".to_string(),
+        Key::Insert => "Insert".to_string(),
+        Key::Delete => "Delete".to_string(),
+        other => format!("{:?}", other),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_layout_is_valid() {
+        for layout in [Layout::Qwerty, Layout::Azerty, Layout::Vi, Layout::Numpad] {
+            let keymap = Keymap::with_layout(layout);
+            assert_eq!(keymap.validate(), Ok(()), "{:?}", layout);
+            // every game action can be played from any layout
+            for action in KeyAction::ALL {
+                assert!(!keymap.keys(action).is_empty(), "{:?} has no key for {:?}", layout, action);
+            }
+        }
+    }
+
+    #[test]
+    fn key_names_keep_the_case_of_letters() {
+        for (name, key) in [
+            ("w", Some(Key::Char('w'))),
+            ("W", Some(Key::Char('W'))),
+            ("alt+W", Some(Key::Alt('W'))),
+            ("ALT+w", Some(Key::Alt('w'))),
+            // the terminal can't tell ctrl+C from ctrl+c
+            ("ctrl+C", Some(Key::Ctrl('c'))),
+            ("f12", Some(Key::F(12))),
+            ("F1", Some(Key::F(1))),
+            ("f13", None),
+            ("Space", Some(Key::Char(' '))),
+            ("shift+UP", Some(Key::ShiftUp)),
+            ("ctrl+", None),
+            ("alt+ab", None),
+            ("hyper", None),
+        ] {
+            assert_eq!(parse_key(name), key, "{}", name);
+        }
+    }
+
+    #[test]
+    fn bad_keymaps_are_rejected() {
+        for (json, error) in [
+            (r#"{ "bindings": { "discover": ["hyper+x"] } }"#, "invalid keymap: unknown key `hyper+x` for `discover`"),
+            (r#"{ "bindings": { "discover": ["w"] } }"#, "invalid keymap: W is bound to both `move_up` and `discover`"),
+            (r#"{ "bindings": { "quit": [] } }"#, "invalid keymap: `quit` needs at least one key"),
+        ] {
+            assert_eq!(Keymap::from_json(json).err().as_deref(), Some(error), "{}", json);
+        }
+        for json in [r#"{ "bindings": { "fly": ["x"] } }"#, r#"{ "layout": "dvorak" }"#, r#"{ "keys": {} }"#] {
+            assert!(Keymap::from_json(json).unwrap_err().starts_with("malformed keymap: "), "{}", json);
+        }
+    }
+
+    #[test]
+    fn rebinding_replaces_the_layout_keys() {
+        let keymap = Keymap::from_json(r#"{ "bindings": { "discover": ["space", "F5"] } }"#).unwrap();
+        assert_eq!(keymap.keys(KeyAction::Discover), [Key::Char(' '), Key::F(5)]);
+        assert_eq!(keymap.action(Key::Char('f')), None);
+        assert_eq!(keymap.label(KeyAction::Discover), "Space, F5");
+    }
+
+    #[test]
+    fn shipped_vi_keymap_loads() {
+        let keymap = Keymap::from_json(include_str!("../keymaps/vi.json")).unwrap();
+        assert_eq!(keymap.action(Key::Char('k')), Some(KeyAction::MoveUp));
+        assert_eq!(keymap.action(Key::Char('K')), Some(KeyAction::CaptureUp));
+        assert_eq!(keymap.action(Key::Char(' ')), Some(KeyAction::Discover));
+        assert_eq!(keymap.action(Key::Ctrl('q')), Some(KeyAction::Quit));
+    }
+}
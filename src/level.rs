@@ -1,38 +1,73 @@
 use std::collections::HashMap;
-use rand::{seq::{IteratorRandom, SliceRandom}, Rng};
+use rand::{seq::SliceRandom, Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
 use wasm_bindgen::prelude::*;
 
-use crate::{entities::{Attacker, Damageable, Fisherman, Marlin, Shark}, map::{HexCell, HexCoord, HexDir}};
+use crate::{campaign::{Campaign, DayCycle, LevelConfig, SpawnCurve, WorldConfig}, clock::TimeOfDay, event::{Creature, GameEvent, WasmGameEvent}, entities::{ActionError, Attacker, Damageable, Fisherman, Marlin, Shark}, map::{hex_map_entries, HexCell, HexCoord, HexDir}, ocean::Ocean, policy::LevelView, terrain::{Terrain, TerrainMap, TerrainType}, replay::{Replay, ReplayTurn}, score::LevelScore, shop::{Equipment, Upgrade}, weather::{Weather, WeatherKind}};
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub enum UserAction {
     Move(HexDir),
     Discover,
     Capture(HexDir),
-    Attack(HexCoord, usize)
+    Attack(HexCoord, usize),
+    /// buy the next level of an upgrade in the harbor shop, takes no turn
+    Buy(Upgrade),
 }
-pub trait UserInterface {
-    fn new() -> Self;
-    fn render(&mut self, game: &Game);
-    fn start(&mut self);
-    fn invalid_input(&mut self);
-    fn prompt(&mut self, msg: String);
+impl std::fmt::Display for UserAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UserAction::Move(HexDir::ZERO) => write!(f, "Stay"),
+            UserAction::Move(dir) => write!(f, "Move {}", dir),
+            UserAction::Discover => write!(f, "Discover"),
+            UserAction::Capture(HexDir::ZERO) => write!(f, "Capture here"),
+            UserAction::Capture(dir) => write!(f, "Capture {}", dir),
+            UserAction::Attack(coord, index) => write!(f, "Attack shark #{} at ({}, {}, {})", index, coord.q, coord.r, coord.s),
+            UserAction::Buy(upgrade) => write!(f, "Buy {}", upgrade),
+        }
+    }
 }
 
 
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Level {
     target: usize,
+    #[serde(with = "hex_map_entries")]
     map: HashMap<HexCoord, HexCell>,
     fisherman: Fisherman,
-    marlin_spawn_probability: Box<dyn Fn(i32) -> f32>,
-    shark_spawn_probability: Box<dyn Fn(i32) -> f32>,
+    marlin_spawn_rate: SpawnCurve,
+    shark_spawn_rate: SpawnCurve,
+    // None: everything out of sight is despawned every turn
+    #[serde(default)]
+    world: Option<WorldConfig>,
+    #[serde(default)]
+    terrain: TerrainMap,
+    #[serde(default)]
+    ocean: Ocean,
+    // every random decision of the level is drawn from here, so a seed fully determines the level
+    rng: ChaCha8Rng,
+    // number of turns played, refused actions don't count
+    #[serde(default)]
+    turn: usize,
+    #[serde(default)]
+    sharks_killed: usize,
+    #[serde(default)]
+    turn_limit: Option<usize>,
+    #[serde(default)]
+    day_cycle: Option<DayCycle>,
+    #[serde(default)]
+    weather: Weather,
+    // what happened during the last turn
+    #[serde(skip)]
+    events: Vec<GameEvent>,
 }
-fn sample_poisson(lambda: f32) -> usize {
+fn sample_poisson(lambda: f32, rng: &mut impl Rng) -> usize {
     let exp_lambda = (-lambda).exp();
     let mut result = 0;
     let mut p = 1.0;
     loop {
-        p *= rand::thread_rng().gen::<f32>();
+        p *= rng.gen::<f32>();
         if p < exp_lambda {
             return result;
         }
@@ -43,18 +78,46 @@ fn sample_poisson(lambda: f32) -> usize {
 impl Level {
     const MARLIN_SPAWN_RADIUS: i32 = Fisherman::VISUAL_RADIUS + Marlin::MOVE_RADIUS;
     const SHARK_SPAWN_RADIUS: i32 = Fisherman::VISUAL_RADIUS + Shark::MOVE_RADIUS;
+    // in a persistent world, creatures farther than this from the fisherman rest instead of moving, to keep turns cheap.
+    // sharks any closer may still smell a hurt marlin or spot the fisherman soon.
+    const ACTIVE_RADIUS: i32 = Fisherman::VISUAL_RADIUS + Shark::SMELL_RADIUS;
+    const WIND_DRIFT_CHANCE: f32 = 0.5;
 
-    pub fn new(target: usize, initial_hp: i32, attack_power: i32, capture_success_rate: f32, marlin_spawn_probability: Box<dyn Fn(i32) -> f32>, shark_spawn_probability: Box<dyn Fn(i32) -> f32>) -> Self {
+    pub fn new(config: &LevelConfig, seed: u64) -> Self {
         Self {
-            target,
+            target: config.target,
             map: HashMap::new(),
-            fisherman: Fisherman::new(initial_hp, attack_power, capture_success_rate),
-            marlin_spawn_probability,
-            shark_spawn_probability
+            fisherman: Fisherman::new(config.start(), config.initial_hp, config.attack_power, config.capture_success_rate),
+            marlin_spawn_rate: config.marlin_spawn_rate.clone(),
+            shark_spawn_rate: config.shark_spawn_rate.clone(),
+            world: config.world,
+            terrain: TerrainMap::new(&config.terrain, &config.harbors, seed),
+            ocean: Ocean::new(&config.ocean, seed),
+            rng: ChaCha8Rng::seed_from_u64(seed),
+            turn: 0,
+            sharks_killed: 0,
+            turn_limit: config.turn_limit,
+            day_cycle: config.day_cycle,
+            weather: Weather::new(&config.weather, seed),
+            events: vec![],
         }
     }
+    // HashMap iteration order differs between instances, sort it so that the rng is consumed in the same order every run
+    fn sorted_coords(&self) -> Vec<HexCoord> {
+        let mut coords = self.map.keys().copied().collect::<Vec<_>>();
+        coords.sort();
+        coords
+    }
+    fn in_world(world: &Option<WorldConfig>, terrain: &TerrainMap, coord: &HexCoord) -> bool {
+        world.is_none_or(|w| w.contains(terrain, coord))
+    }
+    // creatures far away in a persistent world stay put
+    fn is_active(&self, coord: &HexCoord) -> bool {
+        self.world.is_none() || self.fisherman.get_coord().distance(coord) <= Self::ACTIVE_RADIUS
+    }
     fn action_marlins(&mut self) {
-        let marlins_to_move = self.map.keys().copied().collect::<Vec<_>>().into_iter().flat_map(|c| {
+        let active_coords = self.sorted_coords().into_iter().filter(|c| self.is_active(c)).collect::<Vec<_>>();
+        let marlins_to_move = active_coords.into_iter().flat_map(|c| {
             let Some(cell) = self.map.get_mut(&c) else {
                 return vec![]
             };
@@ -64,9 +127,16 @@ impl Level {
         // Process each marlin's movement
         for (current_coord, marlin) in marlins_to_move {
             // Get possible neighboring coordinates
-            let neighbors = current_coord.within_radius(Marlin::MOVE_RADIUS).into_iter().filter(|p| *p != Fisherman::HARBOR_COORD).collect::<Vec<_>>(); // Get neighbors within 1 radius, but not harbor
-            // randomly choose a neighbor with least sharks
-            let new_coord = neighbors.choose(&mut rand::thread_rng()).unwrap();
+            let neighbors = current_coord.within_radius(Marlin::MOVE_RADIUS).into_iter().filter(|p| !self.terrain.is_harbor(p) && Self::in_world(&self.world, &self.terrain, p) && self.terrain.at(p).marlins_can_enter()).collect::<Vec<_>>(); // Get neighbors within 1 radius, but not harbor, reefs, nor out of the world
+            // randomly choose a neighbor with least sharks, the wind often carries marlins along
+            let downwind = self.weather.wind().map(|d| current_coord + d).filter(|c| neighbors.contains(c));
+            let new_coord = match &downwind {
+                Some(c) if self.rng.gen::<f32>() < Self::WIND_DRIFT_CHANCE => c,
+                _ => neighbors.choose(&mut self.rng).unwrap(),
+            };
+            if *new_coord != current_coord {
+                self.events.push(GameEvent::MarlinMoved { from: current_coord, to: *new_coord });
+            }
             if let Some(cell) = self.map.get_mut(new_coord) {
                 cell.marlins.push(marlin);
             } else {
@@ -82,10 +152,17 @@ impl Level {
         let fisherman_coord = self.fisherman.get_coord();
         // if Fisherman's position has a shark, each shark in the position will attack the fisherman
         if let Some(cell) = self.map.get(&fisherman_coord) {
-            cell.sharks.iter().for_each(|s| s.attack(&mut self.fisherman));
+            cell.sharks.iter().for_each(|s| {
+                s.attack(&mut self.fisherman);
+                self.events.push(GameEvent::SharkAttackedPlayer { coord: fisherman_coord, hp_left: self.fisherman.get_hp() });
+            });
         }
         // if shark's position has marlins, it will randomly attack one of the marlins.
-        let sharks_to_move = self.map.keys().copied().collect::<Vec<_>>().into_iter().filter(|p| *p != fisherman_coord).flat_map(|c| {
+        let world = self.world;
+        let terrain = &self.terrain;
+        let shark_sight = Shark::visual_radius(self.time_of_day());
+        let active_coords = self.sorted_coords().into_iter().filter(|c| self.is_active(c)).collect::<Vec<_>>();
+        let sharks_to_move = active_coords.into_iter().filter(|p| *p != fisherman_coord).flat_map(|c| {
             let Some(cell) = self.map.get_mut(&c) else {
                 return vec![]
             };
@@ -98,29 +175,22 @@ impl Level {
                 // attack marlins in the cell
                 if !marlins.is_empty() {
                     for s in sharks {
-                        s.attack(marlins.choose_mut(&mut rand::thread_rng()).unwrap());
+                        let marlin = marlins.choose_mut(&mut self.rng).unwrap();
+                        s.attack(marlin);
+                        self.events.push(GameEvent::SharkAttackedMarlin { coord: c, hp_left: marlin.get_hp() });
                     }
                     return vec![];
                 }
             }
             // with move
             let sharks = cell.sharks.drain(..).collect::<Vec<_>>();
-            let move_targets = c.within_radius(Shark::MOVE_RADIUS).into_iter().filter(|p| *p != Fisherman::HARBOR_COORD);
+            let passable = |p: &HexCoord| !terrain.is_harbor(p) && Self::in_world(&world, terrain, p) && terrain.at(p).sharks_can_enter();
+            let move_targets = c.within_radius(Shark::MOVE_RADIUS).into_iter().filter(passable).collect::<Vec<_>>();
 
-            if fisherman_coord.distance(&c) <= Shark::VISUAL_RADIUS {
+            if fisherman_coord.distance(&c) <= shark_sight {
                 // if fisherman is within the visual radius of shark, shark will swim to the closest position
-                let mut closest_dist = i32::MAX;
-                let mut closest_coords = vec![];
-                for t in move_targets {
-                    let new_dist = fisherman_coord.distance(&t);
-                    if new_dist < closest_dist {
-                        closest_dist = new_dist;
-                        closest_coords = vec![t]
-                    } else if new_dist == closest_dist {
-                        closest_coords.push(t);
-                    }
-                }
-                sharks.into_iter().map(|s| (s, *closest_coords.choose(&mut rand::thread_rng()).unwrap())).collect()
+                let closest_coords = Self::closest_steps(&move_targets, &fisherman_coord, passable);
+                sharks.into_iter().map(|s| (c, s, *closest_coords.choose(&mut self.rng).unwrap())).collect()
             } else {
                 // if there is hurt marlins within smell radius of shark, shark will choose the closest marlin and swim to the closest position to that marlin.
                 for radius in 1..=Shark::SMELL_RADIUS {
@@ -133,29 +203,21 @@ impl Level {
                     if hurt_marlin_positions.is_empty() {
                         continue;
                     }
-                    return sharks.into_iter().map(|s| (s, {
-                        let target_pos = hurt_marlin_positions.choose(&mut rand::thread_rng()).unwrap();
-                        let mut closest_dist = i32::MAX;
-                        let mut closest_coords: Vec<HexCoord> = vec![];
-                        for t in move_targets.clone() {
-                            let new_dist = target_pos.distance(&t);
-                            if new_dist < closest_dist {
-                                closest_dist = new_dist;
-                                closest_coords = vec![t]
-                            } else if new_dist == closest_dist {
-                                closest_coords.push(t);
-                            }
-                        }
-                        *closest_coords.choose(&mut rand::thread_rng()).unwrap()
+                    return sharks.into_iter().map(|s| (c, s, {
+                        let target_pos = hurt_marlin_positions.choose(&mut self.rng).unwrap();
+                        *Self::closest_steps(&move_targets, target_pos, passable).choose(&mut self.rng).unwrap()
                     })).collect()
                 }
                 // there are no marlins and fisherman available, randomly swims.
                 sharks.into_iter().map(|s| 
-                    (s, move_targets.clone().choose(&mut rand::thread_rng()).unwrap())
+                    (c, s, *move_targets.choose(&mut self.rng).unwrap())
                 ).collect()
             }
         }).collect::<Vec<_>>();
-        for (shark, target_coord) in sharks_to_move {
+        for (from, shark, target_coord) in sharks_to_move {
+            if from != target_coord {
+                self.events.push(GameEvent::SharkMoved { from, to: target_coord });
+            }
             if let Some(cell) = self.map.get_mut(&target_coord) {
                 cell.sharks.push(shark);
             } else {
@@ -166,40 +228,107 @@ impl Level {
             }
         }
     }
+    // the move targets closest to `goal` by swimming around impassable cells, a shark picks one of them at random.
+    // with nothing in the way, this is just the hex distance.
+    fn closest_steps(move_targets: &[HexCoord], goal: &HexCoord, passable: impl Fn(&HexCoord) -> bool) -> Vec<HexCoord> {
+        // allow detours up to twice the straight distance
+        let max_steps = move_targets.iter().map(|t| t.distance(goal)).max().unwrap_or(0) * 2;
+        let distances = goal.distances_within(max_steps, passable);
+        let Some(closest) = move_targets.iter().filter_map(|t| distances.get(t)).min() else {
+            // the goal is out of reach, get as close as the crow flies
+            let closest = move_targets.iter().map(|t| t.distance(goal)).min().unwrap();
+            return move_targets.iter().filter(|t| t.distance(goal) == closest).copied().collect();
+        };
+        move_targets.iter().filter(|t| distances.get(t) == Some(closest)).copied().collect()
+    }
     fn kill_died_creatures(&mut self) {
-        for c in self.map.keys().copied().collect::<Vec<_>>() {
+        for c in self.sorted_coords() {
             
             let Some(HexCell{sharks, marlins}) = &mut self.map.get_mut(&c) else {
                 continue;
             };
+            let shark_num = sharks.len();
             *sharks = sharks.drain(..).filter(|p| p.is_alive()).collect();
+            // only the fisherman hurts sharks
+            self.sharks_killed += shark_num - sharks.len();
+            self.events.extend((sharks.len()..shark_num).map(|_| GameEvent::SharkKilled { coord: c }));
+            let marlin_num = marlins.len();
             *marlins = marlins.drain(..).filter(|p| p.is_alive()).collect();
+            self.events.extend((marlins.len()..marlin_num).map(|_| GameEvent::MarlinDied { coord: c }));
 
         }
     }
     fn despawn_cells(&mut self) {
+        // a persistent world only forgets empty cells
+        let fisherman_coord = self.fisherman.get_coord();
+        let world = self.world;
+        let terrain = &self.terrain;
+        let keep = |c: &HexCoord| match &world {
+            Some(world) => world.contains(terrain, c),
+            None => fisherman_coord.distance(c) <= Fisherman::VISUAL_RADIUS,
+        };
+        for c in self.sorted_coords() {
+            if keep(&c) {
+                continue;
+            }
+            let cell = &self.map[&c];
+            self.events.extend(cell.marlins.iter().map(|_| GameEvent::CreatureDespawned { coord: c, creature: Creature::Marlin }));
+            self.events.extend(cell.sharks.iter().map(|_| GameEvent::CreatureDespawned { coord: c, creature: Creature::Shark }));
+        }
         self.map.retain(|k, v| {
-            self.fisherman.get_coord().distance(k) <= Fisherman::VISUAL_RADIUS && (!v.marlins.is_empty() || !v.sharks.is_empty())
+            keep(k) && (!v.marlins.is_empty() || !v.sharks.is_empty())
         });
     }
-    fn test_game_over(&self) -> Option<Result<usize, usize>> {
+    fn test_game_over(&mut self) -> Option<Result<usize, usize>> {
         // test alive first
         if !self.fisherman.is_alive() {
             return Some(Err(self.fisherman.get_captured_marlins()))
         }
-        if self.fisherman.get_coord() == Fisherman::HARBOR_COORD && self.fisherman.get_captured_marlins() >= self.target {
+        // any harbor will do
+        if self.terrain.is_harbor(&self.fisherman.get_coord()) && self.fisherman.get_captured_marlins() >= self.target {
             return Some(Ok(self.fisherman.get_captured_marlins()))
         }
+        if self.turns_left() == Some(0) {
+            self.events.push(GameEvent::OutOfTime);
+            return Some(Err(self.fisherman.get_captured_marlins()))
+        }
         None
     }
+    // creatures per region, only tracked in a persistent world
+    fn region_populations(&self) -> HashMap<(i32, i32), usize> {
+        let mut populations = HashMap::new();
+        if let Some(world) = &self.world {
+            for (c, cell) in &self.map {
+                *populations.entry(world.region_of(c)).or_default() += cell.marlins.len() + cell.sharks.len();
+            }
+        }
+        populations
+    }
+    // clamps the number of creatures to spawn at `cell` to what the world allows, and counts them in
+    fn admit(world: &Option<WorldConfig>, terrain: &TerrainMap, populations: &mut HashMap<(i32, i32), usize>, cell: &HexCoord, num: usize) -> usize {
+        let Some(world) = world else {
+            return num;
+        };
+        if !world.contains(terrain, cell) {
+            return 0;
+        }
+        let population = populations.entry(world.region_of(cell)).or_default();
+        let num = num.min(world.region_cap.saturating_sub(*population));
+        *population += num;
+        num
+    }
     fn spawn_new_creatures(&mut self) {
+        let mut populations = self.region_populations();
         for radius in Fisherman::VISUAL_RADIUS+1..=Self::MARLIN_SPAWN_RADIUS {
             let cells = self.fisherman.get_coord().on_radius(radius);
             for cell in cells {
-                let from_center = cell.distance(&Fisherman::HARBOR_COORD);
-                let lambda = (self.marlin_spawn_probability)(from_center);
-                let num = sample_poisson(lambda);
+                let from_center = self.terrain.harbor_distance(&cell);
+                let lambda = self.marlin_spawn_rate.eval(from_center) * self.ocean.marlin_factor(&cell);
+                let num = sample_poisson(lambda, &mut self.rng);
+                let num = if self.terrain.at(&cell).marlins_can_enter() && !self.terrain.is_harbor(&cell) { num } else { 0 };
+                let num = Self::admit(&self.world, &self.terrain, &mut populations, &cell, num);
                 let marlins = vec![Marlin::new(); num];
+                self.events.extend((0..num).map(|_| GameEvent::CreatureSpawned { coord: cell, creature: Creature::Marlin }));
                 
                 if let Some(v) = self.map.get_mut(&cell) {
                     v.marlins.extend(marlins)
@@ -211,10 +340,13 @@ impl Level {
         for radius in Fisherman::VISUAL_RADIUS+1..=Self::SHARK_SPAWN_RADIUS {
             let cells = self.fisherman.get_coord().on_radius(radius);
             for cell in cells {
-                let from_center = cell.distance(&Fisherman::HARBOR_COORD);
-                let lambda = (self.shark_spawn_probability)(from_center);
-                let num = sample_poisson(lambda);
+                let from_center = self.terrain.harbor_distance(&cell);
+                let lambda = self.shark_spawn_rate.eval(from_center) * self.ocean.shark_factor(&cell);
+                let num = sample_poisson(lambda, &mut self.rng);
+                let num = if self.terrain.at(&cell).sharks_can_enter() && !self.terrain.is_harbor(&cell) { num } else { 0 };
+                let num = Self::admit(&self.world, &self.terrain, &mut populations, &cell, num);
                 let sharks = vec![Shark::new(); num];
+                self.events.extend((0..num).map(|_| GameEvent::CreatureSpawned { coord: cell, creature: Creature::Shark }));
 
                 if let Some(v) = self.map.get_mut(&cell) {
                     v.sharks.extend(sharks)
@@ -224,41 +356,37 @@ impl Level {
             }
         }
     }
-    // fn action_player(&mut self, interface: &mut impl UserInterface) {
-    //     interface.render(self.target, self.get_fisherman(), self.get_map());
-    //     loop {
-    //         if match interface.input() {
-    //             crate::interface::UserAction::Move(dir) => self.fisherman.operate(dir),
-    //             crate::interface::UserAction::Discover => self.fisherman.discover_marlins(&mut self.map),
-    //             crate::interface::UserAction::Capture(dir) => self.fisherman.capture_marlins(self.fisherman.get_coord() + dir, &mut self.map),
-    //             crate::interface::UserAction::Attack(coord, index) => {
-    //                 self.fisherman.attack_shark(self.map.get_mut(&coord).and_then(|c| c.sharks.get_mut(index)))
-    //             },
-    //         } {
-    //             break;
-    //         }
-    //         interface.invalid_input();
-    //     }
-    // }
-    // fn turn(&mut self, interface: &mut impl UserInterface) -> Option<Result<usize, usize>> {
-    //     self.spawn_new_creatures();
-    //     self.action_player(interface);
-    //     self.action_marlins();
-    //     self.action_sharks();
-    //     self.kill_died_creatures();
-    //     self.despawn_cells();
-    //     self.test_game_over()
-    // }
-    pub fn advance(&mut self, input: UserAction) -> Result<Option<Result<usize, usize>>, ()> {
-        if !match input {
-            UserAction::Move(dir) => self.fisherman.operate(dir),
-            UserAction::Discover => self.fisherman.discover_marlins(&mut self.map),
-            UserAction::Capture(dir) => self.fisherman.capture_marlins(self.fisherman.get_coord() + dir, &mut self.map),
-            UserAction::Attack(coord, index) => {
-                self.fisherman.attack_shark(self.map.get_mut(&coord).and_then(|c| c.sharks.get_mut(index)))
-            },
-        } {
-            return Err(())
+    pub fn advance(&mut self, input: UserAction) -> Result<Option<Result<usize, usize>>, ActionError> {
+        self.events.clear();
+        let time = self.time_of_day();
+        let weather = self.weather.kind();
+        match input {
+            UserAction::Move(dir) => self.fisherman.operate(dir, &self.terrain),
+            UserAction::Discover => self.fisherman.discover_marlins(&mut self.map, &self.terrain, time, weather),
+            UserAction::Capture(dir) => self.fisherman.capture_marlins(self.fisherman.get_coord() + dir, &mut self.map, &self.terrain, weather, &mut self.rng, &mut self.events),
+            UserAction::Attack(coord, index) => self.fisherman.attack_shark(coord, index, &mut self.map, &self.terrain, &mut self.events),
+            // the shop is run by `Game`, which keeps the coins
+            UserAction::Buy(_) => Err(ActionError::NoShop),
+        }?;
+        let from = self.fisherman.get_coord();
+        if let Some(to) = self.fisherman.drift(&self.terrain) {
+            self.events.push(GameEvent::BoatDrifted { from, to });
+        }
+        // the harbor shelters the boat from gusts
+        let from = self.fisherman.get_coord();
+        if let Some(dir) = self.weather.gust().filter(|_| !self.terrain.is_harbor(&from)) {
+            if let Some(to) = self.fisherman.push(dir, &self.terrain) {
+                self.events.push(GameEvent::BoatBlown { from, to });
+            }
+        }
+        self.turn += 1;
+        if let Some(weather) = self.weather.advance() {
+            self.events.push(GameEvent::WeatherChanged { weather });
+        }
+        match (time, self.time_of_day()) {
+            (TimeOfDay::Day, TimeOfDay::Night) => self.events.push(GameEvent::NightFell),
+            (TimeOfDay::Night, TimeOfDay::Day) => self.events.push(GameEvent::DayBroke),
+            _ => {},
         }
         self.action_marlins();
         self.action_sharks();
@@ -279,20 +407,60 @@ impl Level {
         self.map.clone()
     }
 
+    #[inline]
+    pub fn get_target(&self) -> usize {
+        self.target
+    }
+
+    /// Everything that happened during the last turn, in order.
+    #[inline]
+    pub fn events(&self) -> &[GameEvent] {
+        &self.events
+    }
+
+    /// What the player can see, for policies.
+    pub fn view(&self) -> LevelView<'_> {
+        LevelView::new(&self.fisherman, self.target, &self.map, &self.terrain, self.time_of_day(), self.weather.kind(), self.turns_left())
+    }
+
+    #[inline]
+    pub fn terrain(&self) -> &TerrainMap {
+        &self.terrain
+    }
+
+    #[inline]
+    pub fn get_turn(&self) -> usize {
+        self.turn
+    }
+
+    pub fn time_of_day(&self) -> TimeOfDay {
+        self.day_cycle.map_or(TimeOfDay::Day, |c| c.time_at(self.turn))
+    }
 
-    // pub fn start(&mut self, interface: &mut impl UserInterface) -> Result<usize, usize> {
-    //     loop {
-    //         if let Some(res) = self.turn(interface) {
-    //             return res;
-    //         } else {
-    //             continue;
-    //         }
-    //     }
-    // }
+    /// Turns left before the deadline, if the level has one.
+    pub fn turns_left(&self) -> Option<usize> {
+        self.turn_limit.map(|limit| limit.saturating_sub(self.turn))
+    }
+
+    /// How far the fisherman sees right now.
+    pub fn visual_radius(&self) -> i32 {
+        self.fisherman.visual_radius(self.time_of_day(), self.weather.kind())
+    }
+
+    #[inline]
+    pub fn weather(&self) -> &Weather {
+        &self.weather
+    }
+
+    #[inline]
+    pub fn get_sharks_killed(&self) -> usize {
+        self.sharks_killed
+    }
 }
 
 
 #[wasm_bindgen]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum InputResult {
     InvalidInput,
     Ok,
@@ -300,18 +468,47 @@ pub enum InputResult {
     LevelFailed,
     GamePassed,
 }
+
+/// Starting a level over, outside of the normal flow of turns. Recorded in replays like actions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LevelChange {
+    Retry,
+    /// play the level with this index, only levels reached before can be chosen
+    JumpTo(usize),
+    RestartCampaign,
+}
+impl std::fmt::Display for LevelChange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LevelChange::Retry => write!(f, "Retry level"),
+            LevelChange::JumpTo(index) => write!(f, "Jump to level {}", index + 1),
+            LevelChange::RestartCampaign => write!(f, "Restart campaign"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LevelChangeError {
+    /// the level doesn't exist or hasn't been reached yet
+    Locked { index: usize, furthest: usize },
+}
+impl std::fmt::Display for LevelChangeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LevelChangeError::Locked { index, furthest } => write!(f, "level {} is locked, only levels 1 to {} can be played", index + 1, furthest + 1),
+        }
+    }
+}
+impl std::error::Error for LevelChangeError {}
 #[wasm_bindgen]
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Default)]
 pub enum WasmUserActionType {
+    #[default]
     Move,
     Discover,
     Capture,
     Attack,
-}
-impl Default for WasmUserActionType {
-    fn default() -> Self {
-        Self::Move
-    }
+    Buy,
 }
 
 #[wasm_bindgen]
@@ -351,15 +548,26 @@ impl WasmUserAction {
             param_1: index,
         }
     }
+    pub fn buy_action(upgrade: Upgrade) -> Self {
+        Self {
+            action_type: WasmUserActionType::Buy,
+            param_0: HexCoord::ZERO,
+            param_1: upgrade as usize,
+        }
+    }
 }
-impl Into<UserAction> for WasmUserAction {
-    fn into(self) -> UserAction {
-        match self.action_type {
-            WasmUserActionType::Move => UserAction::Move(self.param_0 - HexCoord::ZERO),
+// the parameters come from JS, so they may not make sense
+impl TryFrom<WasmUserAction> for UserAction {
+    type Error = ActionError;
+
+    fn try_from(value: WasmUserAction) -> Result<Self, ActionError> {
+        Ok(match value.action_type {
+            WasmUserActionType::Move => UserAction::Move(value.param_0 - HexCoord::ZERO),
             WasmUserActionType::Discover => UserAction::Discover,
-            WasmUserActionType::Capture => UserAction::Capture(self.param_0 - HexCoord::ZERO),
-            WasmUserActionType::Attack => UserAction::Attack(self.param_0, self.param_1),
-        }
+            WasmUserActionType::Capture => UserAction::Capture(value.param_0 - HexCoord::ZERO),
+            WasmUserActionType::Attack => UserAction::Attack(value.param_0, value.param_1),
+            WasmUserActionType::Buy => UserAction::Buy(*Upgrade::ALL.get(value.param_1).ok_or(ActionError::NoSuchUpgrade { index: value.param_1 })?),
+        })
     }
 }
 
@@ -386,30 +594,344 @@ impl From<UserAction> for WasmUserAction {
                 param_0: hex_coord,
                 param_1: index
             },
+            UserAction::Buy(upgrade) => Self::buy_action(upgrade),
         }
     }
 }
 #[wasm_bindgen]
 pub struct Game {
     current_level: Level,
-    current_score: usize,
-    levels: std::vec::IntoIter<Level>,
+    // latest score of each level passed since the campaign was (re)started, in the order they were passed
+    scores: Vec<LevelScore>,
+    level_index: usize,
+    // highest level index started so far, the player may jump back to any level up to it
+    furthest_level: usize,
+    // how many times each level has been started, retries of a level get a different ocean
+    attempts: Vec<usize>,
+    seed: u64,
+    campaign: Campaign,
+    // every action handled so far, with the result it got
+    log: Vec<ReplayTurn>,
+    // level changes, each with the number of actions handled before it
+    level_changes: Vec<(usize, LevelChange)>,
+    // earned with surplus marlins, spent in the harbor shop
+    coins: usize,
+    // the most surplus marlins each level has been paid for, replaying a level only pays for doing better
+    paid_surplus: Vec<usize>,
+    // everything bought so far, carried from level to level
+    equipment: Equipment,
+    // why the last action was refused, if it was
+    last_error: Option<ActionError>,
+    // what happened during the last turn, kept here since the level may have been replaced since
+    events: Vec<GameEvent>,
+}
+
+/// Everything needed to resume a game. Bump `Game::SAVE_VERSION` on incompatible changes.
+#[derive(Serialize, Deserialize)]
+struct GameSnapshot {
+    version: u32,
+    seed: u64,
+    level_index: usize,
+    furthest_level: usize,
+    attempts: Vec<usize>,
+    scores: Vec<LevelScore>,
+    current_level: Level,
+    campaign: Campaign,
+    log: Vec<ReplayTurn>,
+    level_changes: Vec<(usize, LevelChange)>,
+    coins: usize,
+    paid_surplus: Vec<usize>,
+    equipment: Equipment,
+}
+
+impl Game {
+    pub fn with_campaign(campaign: &Campaign, seed: u64) -> Self {
+        let config = campaign.levels.first().expect("campaign has no levels");
+        let mut attempts = vec![0; campaign.levels.len()];
+        attempts[0] = 1;
+        Self {
+            current_level: Level::new(config, Self::level_seed(seed, 0, 0)),
+            level_index: 0,
+            furthest_level: 0,
+            attempts,
+            scores: vec![],
+            seed,
+            campaign: campaign.clone(),
+            log: vec![],
+            level_changes: vec![],
+            coins: 0,
+            paid_surplus: vec![0; campaign.levels.len()],
+            equipment: Equipment::default(),
+            last_error: None,
+            events: vec![],
+        }
+    }
+
+    fn level_seed(seed: u64, index: usize, attempt: usize) -> u64 {
+        // each level gets its own seed derived from the game seed
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        let level_seed = (0..=index).map(|_| rng.gen::<u64>()).last().unwrap();
+        if attempt == 0 {
+            return level_seed;
+        }
+        // and each retry a different one, still determined by the game seed
+        let mut rng = ChaCha8Rng::seed_from_u64(level_seed);
+        rng.set_stream(attempt as u64);
+        rng.gen()
+    }
+
+    fn start_level(&mut self, index: usize) {
+        let attempt = self.attempts[index];
+        self.attempts[index] += 1;
+        self.current_level = Level::new(&self.campaign.levels[index], Self::level_seed(self.seed, index, attempt));
+        self.current_level.fisherman.equip(self.equipment);
+        self.level_index = index;
+        self.furthest_level = self.furthest_level.max(index);
+    }
+
+    /// Leaves the current level, finished or not, and starts another one from scratch.
+    pub fn change_level(&mut self, change: LevelChange) -> Result<(), LevelChangeError> {
+        match change {
+            LevelChange::Retry => self.start_level(self.level_index),
+            LevelChange::JumpTo(index) => {
+                if index > self.furthest_level {
+                    return Err(LevelChangeError::Locked { index, furthest: self.furthest_level });
+                }
+                self.start_level(index);
+            },
+            LevelChange::RestartCampaign => {
+                self.scores.clear();
+                self.coins = 0;
+                self.paid_surplus.fill(0);
+                self.equipment = Equipment::default();
+                self.start_level(0);
+            },
+        }
+        self.last_error = None;
+        self.events.clear();
+        self.level_changes.push((self.log.len(), change));
+        Ok(())
+    }
+
+    /// Scores of the levels passed since the campaign was (re)started, replaying a level replaces its score.
+    #[inline]
+    pub fn scores(&self) -> &[LevelScore] {
+        &self.scores
+    }
+
+    /// How many times the level with this index has been started over.
+    pub fn retries_at(&self, index: usize) -> usize {
+        self.attempts.get(index).map_or(0, |a| a.saturating_sub(1))
+    }
+
+    #[inline]
+    pub fn view(&self) -> LevelView<'_> {
+        self.current_level.view()
+    }
+
+    /// Everything that happened during the last turn, in order.
+    #[inline]
+    pub fn events(&self) -> &[GameEvent] {
+        &self.events
+    }
+
+    /// Why the last `handle_action` returned `InputResult::InvalidInput`.
+    #[inline]
+    pub fn last_error(&self) -> Option<ActionError> {
+        self.last_error
+    }
+
+    /// Buys the next level of `upgrade`, only possible in a harbor.
+    pub fn buy(&mut self, upgrade: Upgrade) -> Result<(), ActionError> {
+        if !self.current_level.terrain.is_harbor(&self.current_level.fisherman.get_coord()) {
+            return Err(ActionError::NotInHarbor);
+        }
+        let price = self.equipment.next_price(upgrade).ok_or(ActionError::FullyUpgraded { upgrade })?;
+        if price > self.coins {
+            return Err(ActionError::NotEnoughCoins { price, coins: self.coins });
+        }
+        self.coins -= price;
+        self.equipment.upgrade(upgrade);
+        self.current_level.fisherman.equip(self.equipment);
+        Ok(())
+    }
+
+    #[inline]
+    pub fn equipment(&self) -> &Equipment {
+        &self.equipment
+    }
+
+    fn advance(&mut self, input: UserAction) -> InputResult {
+        if let UserAction::Buy(upgrade) = input {
+            return match self.buy(upgrade) {
+                Ok(()) => InputResult::Ok,
+                Err(e) => {
+                    self.last_error = Some(e);
+                    InputResult::InvalidInput
+                },
+            };
+        }
+        let res = match self.current_level.advance(input) {
+            Ok(res) => res,
+            Err(e) => {
+                self.last_error = Some(e);
+                return InputResult::InvalidInput;
+            },
+        };
+        self.events = self.current_level.events().to_vec();
+        let Some(game_over) = res else { return InputResult::Ok };
+        match game_over {
+            Ok(_) => {
+                self.record_pass(LevelScore::of(self.level_index, &self.current_level));
+                if self.level_index + 1 >= self.campaign.levels.len() {
+                    return InputResult::GamePassed;
+                }
+                self.start_level(self.level_index + 1);
+                InputResult::LevelPassed
+            },
+            // a failed level scores nothing
+            Err(_) => InputResult::LevelFailed,
+        }
+    }
+}
+
+impl Game {
+    const SAVE_VERSION: u32 = 5;
+    const COINS_PER_SURPLUS_MARLIN: usize = 10;
+
+    // keeps the latest score of the level, and pays for the surplus marlins it wasn't paid for yet
+    fn record_pass(&mut self, score: LevelScore) {
+        let paid = &mut self.paid_surplus[score.level_index];
+        self.coins += score.surplus.saturating_sub(*paid) * Self::COINS_PER_SURPLUS_MARLIN;
+        *paid = (*paid).max(score.surplus);
+        self.scores.retain(|s| s.level_index != score.level_index);
+        self.scores.push(score);
+    }
+}
+
+impl Default for Game {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 #[wasm_bindgen]
 impl Game {
     #[wasm_bindgen(constructor)]
     pub fn new() -> Self {
-    
-        let level0 = Level::new(5, 5, 1, 0.5, Box::new(|_| 0.3), Box::new(|_| 0.0));
-        let level1 = Level::new(10, 5, 1, 0.5, Box::new(|_| 0.3), Box::new(|_| 0.05));
-        let level2 = Level::new(15, 5, 1, 0.5, Box::new(|_| 0.25), Box::new(|_| 0.07));
-        let level_iter = vec![level1, level2].into_iter();
-        Self {
-            current_level: level0,
-            levels: level_iter,
-            current_score: 0,
+        Self::with_seed(rand::random())
+    }
+
+    /// Same seed and same actions always give the same game.
+    pub fn with_seed(seed: u64) -> Self {
+        Self::with_campaign(&Campaign::default(), seed)
+    }
+
+    /// Loads a campaign in the JSON level format, returns a description of the bad field if it is rejected.
+    pub fn from_campaign(json: &str, seed: u64) -> Result<Game, String> {
+        let campaign = Campaign::from_json(json).map_err(|e| e.to_string())?;
+        Ok(Self::with_campaign(&campaign, seed))
+    }
+
+    #[inline]
+    pub fn get_seed(&self) -> u64 {
+        self.seed
+    }
+
+    #[inline]
+    pub fn get_level_index(&self) -> usize {
+        self.level_index
+    }
+
+    #[inline]
+    pub fn get_level_count(&self) -> usize {
+        self.campaign.levels.len()
+    }
+
+    /// Highest level index reached, `jump_to_level` accepts anything up to it.
+    #[inline]
+    pub fn get_furthest_level(&self) -> usize {
+        self.furthest_level
+    }
+
+    pub fn get_retries_at(&self, index: usize) -> usize {
+        self.retries_at(index)
+    }
+
+    pub fn get_total_retries(&self) -> usize {
+        (0..self.attempts.len()).map(|i| self.retries_at(i)).sum()
+    }
+
+    /// Starts the current level over, e.g. after failing it.
+    pub fn retry_level(&mut self) {
+        self.change_level(LevelChange::Retry).unwrap();
+    }
+
+    pub fn jump_to_level(&mut self, index: usize) -> Result<(), String> {
+        self.change_level(LevelChange::JumpTo(index)).map_err(|e| e.to_string())
+    }
+
+    /// Starts again from the first level with a score of 0, retry counts are kept.
+    pub fn restart_campaign(&mut self) {
+        self.change_level(LevelChange::RestartCampaign).unwrap();
+    }
+
+    /// Serializes the whole game, including the RNG state, so that a loaded game continues exactly like this one would.
+    pub fn save(&self) -> String {
+        serde_json::to_string(&GameSnapshot {
+            version: Self::SAVE_VERSION,
+            seed: self.seed,
+            level_index: self.level_index,
+            furthest_level: self.furthest_level,
+            attempts: self.attempts.clone(),
+            scores: self.scores.clone(),
+            current_level: self.current_level.clone(),
+            campaign: self.campaign.clone(),
+            log: self.log.clone(),
+            level_changes: self.level_changes.clone(),
+            coins: self.coins,
+            paid_surplus: self.paid_surplus.clone(),
+            equipment: self.equipment,
+        }).unwrap()
+    }
+
+    pub fn load(data: &str) -> Result<Game, String> {
+        // check the version alone first, so an outdated save isn't reported as a random missing field
+        #[derive(Deserialize)]
+        struct Header {
+            version: u32,
+        }
+        let header: Header = serde_json::from_str(data).map_err(|e| format!("malformed save: {}", e))?;
+        if header.version != Self::SAVE_VERSION {
+            return Err(format!("unsupported save version {}, expected {}", header.version, Self::SAVE_VERSION));
         }
+        let snapshot: GameSnapshot = serde_json::from_str(data).map_err(|e| format!("malformed save: {}", e))?;
+        // both are indexed by level
+        let levels = snapshot.campaign.levels.len();
+        if snapshot.attempts.len() != levels || snapshot.paid_surplus.len() != levels {
+            return Err(format!("inconsistent save: {} levels, but {} attempt counts and {} paid surpluses", levels, snapshot.attempts.len(), snapshot.paid_surplus.len()));
+        }
+        Ok(Self {
+            current_level: snapshot.current_level,
+            scores: snapshot.scores,
+            level_index: snapshot.level_index,
+            furthest_level: snapshot.furthest_level,
+            attempts: snapshot.attempts,
+            seed: snapshot.seed,
+            campaign: snapshot.campaign,
+            log: snapshot.log,
+            level_changes: snapshot.level_changes,
+            coins: snapshot.coins,
+            paid_surplus: snapshot.paid_surplus,
+            equipment: snapshot.equipment,
+            last_error: None,
+            events: vec![],
+        })
+    }
+
+    /// Everything played so far, replayable with `Replay::new_game`.
+    pub fn get_replay(&self) -> Replay {
+        Replay::new(self.seed, self.campaign.clone(), self.log.clone(), self.level_changes.clone())
     }
 
     #[inline]
@@ -422,7 +944,64 @@ impl Game {
         self.current_level.target
     }
 
+    /// Whether `coord` is within sight, creatures out of sight are not shown.
+    pub fn is_visible(&self, coord: &HexCoord) -> bool {
+        self.current_level.fisherman.get_coord().distance(coord) <= self.current_level.visual_radius()
+    }
+
+    pub fn get_visual_radius(&self) -> i32 {
+        self.current_level.visual_radius()
+    }
+
+    pub fn get_turn(&self) -> usize {
+        self.current_level.turn
+    }
+
+    /// Turns left before the deadline, none if the level has no turn limit.
+    pub fn get_turns_left(&self) -> Option<usize> {
+        self.current_level.turns_left()
+    }
+
+    pub fn get_time_of_day(&self) -> TimeOfDay {
+        self.current_level.time_of_day()
+    }
+
+    pub fn get_coins(&self) -> usize {
+        self.coins
+    }
+
+    pub fn get_upgrade_level(&self, upgrade: Upgrade) -> u32 {
+        self.equipment.level(upgrade)
+    }
+
+    /// Price of the next level of `upgrade`, none once it is fully upgraded.
+    pub fn get_upgrade_price(&self, upgrade: Upgrade) -> Option<usize> {
+        self.equipment.next_price(upgrade)
+    }
+
+    /// Whether the shop is open, i.e. the boat is in a harbor.
+    pub fn is_shop_open(&self) -> bool {
+        self.current_level.terrain.is_harbor(&self.current_level.fisherman.get_coord())
+    }
+
+    pub fn get_weather(&self) -> WeatherKind {
+        self.current_level.weather.kind()
+    }
+
+    /// Where the wind blows to, none when there is no wind.
+    pub fn get_wind(&self) -> Option<HexDir> {
+        self.current_level.weather.wind()
+    }
+
+    /// Turns until day breaks or night falls, none if the level has no day cycle.
+    pub fn get_turns_until_dusk_or_dawn(&self) -> Option<usize> {
+        self.current_level.day_cycle.map(|c| c.turns_until_change(self.current_level.turn))
+    }
+
     pub fn get_shark_num_at(&self, coord: &HexCoord) -> usize {
+        if !self.is_visible(coord) {
+            return 0;
+        }
         let Some(cell) = self.current_level.map.get(coord) else {
             return 0;
         };
@@ -430,38 +1009,404 @@ impl Game {
     }
 
     pub fn get_discovered_marlin_num_at(&self, coord: &HexCoord) -> usize {
+        if !self.is_visible(coord) {
+            return 0;
+        }
         let Some(cell) = self.current_level.map.get(coord) else {
             return 0;
         };
         cell.marlins.iter().filter(|t| t.is_discovered()).count()
     }
 
+    pub fn get_terrain_at(&self, coord: &HexCoord) -> TerrainType {
+        self.current_level.terrain.at(coord).kind()
+    }
+
+    pub fn is_harbor(&self, coord: &HexCoord) -> bool {
+        self.current_level.terrain.is_harbor(coord)
+    }
+
+    /// Whether the boat could sail into `coord`, ignoring how far away it is.
+    pub fn boat_can_enter(&self, coord: &HexCoord) -> bool {
+        self.current_level.terrain.at(coord).boat_can_enter()
+    }
+
+    /// The harbor the compass points to.
+    pub fn get_nearest_harbor(&self) -> HexCoord {
+        self.current_level.terrain.nearest_harbor(&self.current_level.fisherman.get_coord())
+    }
+
+    /// Direction of the current at `coord`, if there is one.
+    pub fn get_current_at(&self, coord: &HexCoord) -> Option<HexDir> {
+        match self.current_level.terrain.at(coord) {
+            Terrain::Current(dir) => Some(dir),
+            _ => None,
+        }
+    }
+
     pub fn get_nth_shark_at(&self, coord: &HexCoord, n: usize) -> Option<Shark> {
         Some(self.current_level.map.get(coord)?.sharks.get(n)?.clone())
     }
-    #[inline]
-    pub fn get_score(&self) -> usize{
-        self.current_score
+
+    /// The `n`th marlin at `coord` among the discovered ones in sight.
+    pub fn get_nth_discovered_marlin_at(&self, coord: &HexCoord, n: usize) -> Option<Marlin> {
+        if !self.is_visible(coord) {
+            return None;
+        }
+        self.current_level.map.get(coord)?.marlins.iter().filter(|m| m.is_discovered()).nth(n).copied()
+    }
+    /// Campaign score: the sum of the scores of all levels passed.
+    pub fn get_score(&self) -> usize {
+        self.scores.iter().map(|s| s.total()).sum()
+    }
+
+    pub fn get_level_scores(&self) -> Vec<LevelScore> {
+        self.scores.clone()
+    }
+
+    pub fn get_score_of(&self, level_index: usize) -> Option<LevelScore> {
+        self.scores.iter().find(|s| s.level_index == level_index).copied()
     }
+    /// Explanation of the last refused action, for displaying.
+    pub fn get_last_error_message(&self) -> Option<String> {
+        Some(self.last_error?.to_string())
+    }
+
+    pub fn get_events(&self) -> Vec<WasmGameEvent> {
+        self.events.iter().map(|e| (*e).into()).collect()
+    }
+
+    /// One line per notable event of the last turn, for a turn log.
+    pub fn get_event_messages(&self) -> Vec<String> {
+        self.events.iter().filter(|e| e.is_notable()).map(|e| e.to_string()).collect()
+    }
+
+    /// Plays one turn. A refused action takes no turn and returns `InputResult::InvalidInput`, `last_error()` says why.
     pub fn handle_action(&mut self, input: WasmUserAction) -> InputResult {
-        let input = input.into();
-        let Ok(res) = self.current_level.advance(input) else {
-            return InputResult::InvalidInput;
-        };
-        let Some(game_over) = res else { return InputResult::Ok };
-        match game_over {
-            Ok(score) => {
-                self.current_score = score;
-                let Some(next_level) = self.levels.next() else {
-                    return InputResult::GamePassed;
-                };
-                self.current_level = next_level;
-                InputResult::LevelPassed
-            },
-            Err(score) => { 
-                self.current_score = score;
-                InputResult::LevelFailed
+        self.last_error = None;
+        self.events.clear();
+        // nothing to record when it isn't even an action
+        let action = match input.try_into() {
+            Ok(action) => action,
+            Err(e) => {
+                self.last_error = Some(e);
+                return InputResult::InvalidInput;
             },
+        };
+        let result = self.advance(action);
+        self.log.push(ReplayTurn { action, result });
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{campaign::{TerrainConfig, WeatherConfig}, policy::{GreedyHunter, Policy}};
+
+    // plays `turns` actions chosen by a seeded policy, retrying failed levels
+    fn play(game: &mut Game, policy_seed: u64, turns: usize) {
+        let mut policy = GreedyHunter::new(policy_seed);
+        for _ in 0..turns {
+            match game.handle_action(policy.choose_action(&game.view()).into()) {
+                InputResult::LevelFailed | InputResult::GamePassed => game.change_level(LevelChange::Retry).unwrap(),
+                _ => {},
+            }
+        }
+    }
+
+    #[test]
+    fn same_seed_same_game() {
+        let mut a = Game::with_campaign(&Campaign::default(), 42);
+        let mut b = Game::with_campaign(&Campaign::default(), 42);
+        assert_eq!(a.save(), b.save());
+        play(&mut a, 1, 200);
+        play(&mut b, 1, 200);
+        assert_eq!(a.save(), b.save());
+        let mut c = Game::with_campaign(&Campaign::default(), 43);
+        play(&mut c, 1, 200);
+        assert_ne!(a.save(), c.save());
+    }
+
+    #[test]
+    fn loaded_game_plays_on_like_the_original() {
+        let mut game = Game::with_campaign(&Campaign::default(), 7);
+        play(&mut game, 2, 37);
+        assert!(game.get_turn() > 0, "the save should be taken mid-level");
+        let mut loaded = Game::load(&game.save()).unwrap();
+        assert_eq!(loaded.save(), game.save());
+        play(&mut game, 3, 150);
+        play(&mut loaded, 3, 150);
+        assert_eq!(loaded.save(), game.save());
+        assert!(loaded.get_replay().verify().is_ok());
+    }
+
+    #[test]
+    fn save_of_another_version_is_rejected() {
+        let game = Game::with_campaign(&Campaign::default(), 7);
+        let mut save: serde_json::Value = serde_json::from_str(&game.save()).unwrap();
+        save["version"] = (Game::SAVE_VERSION - 1).into();
+        // the rest of the save doesn't matter once the version is wrong
+        save.as_object_mut().unwrap().remove("campaign");
+        assert_eq!(Game::load(&save.to_string()).err().unwrap(), format!("unsupported save version {}, expected {}", Game::SAVE_VERSION - 1, Game::SAVE_VERSION));
+    }
+
+    #[test]
+    fn level_reports_captures_attacks_and_drift() {
+        // nothing spawns and every throw of the net catches, so only what the test puts in the sea happens
+        let mut config = Campaign::default().levels[0].clone();
+        config.marlin_spawn_rate = SpawnCurve::Constant { value: 0.0 };
+        config.shark_spawn_rate = SpawnCurve::Constant { value: 0.0 };
+        config.capture_success_rate = 1.0;
+        config.attack_power = 2;
+        let current = HexCoord::ZERO + HexDir::NORTH;
+        config.terrain = TerrainConfig { cells: vec![(current, Terrain::Current(HexDir::NORTHEAST))], generator: None };
+        config.turn_limit = None;
+        config.day_cycle = None;
+        config.weather = None;
+        let mut level = Level::new(&config, 3);
+
+        assert_eq!(level.advance(UserAction::Move(HexDir::NORTH)), Ok(None));
+        let at = current + HexDir::NORTHEAST;
+        assert_eq!(level.events(), [GameEvent::BoatDrifted { from: current, to: at }]);
+        assert_eq!(level.fisherman.get_coord(), at);
+
+        // the marlin swims at most a cell away while it is being spotted, so it stays within reach of the net
+        level.map.entry(at).or_default().marlins.push(Marlin::new());
+        assert_eq!(level.advance(UserAction::Discover), Ok(None));
+        let marlin = *level.map.iter().find(|(_, cell)| !cell.marlins.is_empty()).unwrap().0;
+        assert!(level.map[&marlin].marlins[0].is_discovered());
+        assert_eq!(level.advance(UserAction::Capture(marlin - at)), Ok(None));
+        assert_eq!(level.events()[0], GameEvent::MarlinCaptured { coord: marlin });
+        assert_eq!(level.fisherman.get_captured_marlins(), 1);
+
+        level.map.entry(at).or_default().sharks.push(Shark::new());
+        assert_eq!(level.advance(UserAction::Attack(at, 0)), Ok(None));
+        assert_eq!(level.events()[0], GameEvent::SharkHit { coord: at, hp_left: 0 });
+        assert!(level.events().contains(&GameEvent::SharkKilled { coord: at }));
+        assert_eq!(level.get_sharks_killed(), 1);
+        assert!(level.map.values().all(|cell| cell.marlins.is_empty() && cell.sharks.is_empty()));
+    }
+
+    // a level where nothing spawns, to watch the clock alone
+    fn empty_level(turn_limit: Option<usize>, day_cycle: Option<DayCycle>) -> Level {
+        let mut config = Campaign::default().levels[0].clone();
+        config.marlin_spawn_rate = SpawnCurve::Constant { value: 0.0 };
+        config.shark_spawn_rate = SpawnCurve::Constant { value: 0.0 };
+        config.turn_limit = turn_limit;
+        config.day_cycle = day_cycle;
+        config.weather = None;
+        Level::new(&config, 3)
+    }
+
+    #[test]
+    fn nightfall_and_daybreak_are_reported_on_their_turn() {
+        let mut level = empty_level(None, Some(DayCycle { day_turns: 2, night_turns: 3, start_turn: 0 }));
+        let mut changes = vec![];
+        for turn in 1..=10 {
+            assert_eq!(level.advance(UserAction::Move(HexDir::ZERO)), Ok(None));
+            changes.extend(level.events().iter().filter(|e| matches!(e, GameEvent::NightFell | GameEvent::DayBroke)).map(|e| (turn, *e)));
+        }
+        assert_eq!(changes, [(2, GameEvent::NightFell), (5, GameEvent::DayBroke), (7, GameEvent::NightFell), (10, GameEvent::DayBroke)]);
+    }
+
+    #[test]
+    fn forecast_ignores_what_the_player_does() {
+        let mut config = Campaign::default().levels[0].clone();
+        config.weather = Some(WeatherConfig { calm: 1.0, wind: 1.0, fog: 1.0, storm: 2.0, min_turns: 1, max_turns: 3 });
+        let forecast = |policy_seed| {
+            let mut level = Level::new(&config, 8);
+            let mut policy = GreedyHunter::new(policy_seed);
+            let mut kinds = vec![];
+            for _ in 0..60 {
+                if level.advance(policy.choose_action(&level.view())).is_ok_and(|over| over.is_some()) {
+                    break;
+                }
+                kinds.push(level.weather().kind());
+            }
+            kinds
+        };
+        let (a, b) = (forecast(1), forecast(2));
+        let turns = a.len().min(b.len());
+        assert!(turns > 10);
+        assert_eq!(a[..turns], b[..turns]);
+        assert!(a.contains(&WeatherKind::Storm));
+    }
+
+    #[test]
+    fn out_of_time_when_the_last_turn_ends_at_sea() {
+        let mut level = empty_level(Some(3), None);
+        assert_eq!(level.turns_left(), Some(3));
+        for turns_left in [2, 1] {
+            assert_eq!(level.advance(UserAction::Move(HexDir::NORTH)), Ok(None));
+            assert_eq!(level.turns_left(), Some(turns_left));
+            assert!(!level.events().contains(&GameEvent::OutOfTime));
+        }
+        assert_eq!(level.advance(UserAction::Move(HexDir::ZERO)), Ok(Some(Err(0))));
+        assert_eq!(level.turns_left(), Some(0));
+        assert_eq!(level.events().last(), Some(&GameEvent::OutOfTime));
+        // without a limit there is all the time in the world
+        let mut level = empty_level(None, None);
+        for _ in 0..20 {
+            assert_eq!(level.advance(UserAction::Move(HexDir::NORTH)), Ok(None));
+        }
+        assert_eq!(level.turns_left(), None);
+    }
+
+    fn persistent_level(marlin_rate: f32, shark_rate: f32) -> LevelConfig {
+        let mut config = Campaign::from_json(include_str!("../campaigns/persistent.json")).unwrap().levels[0].clone();
+        config.marlin_spawn_rate = SpawnCurve::Constant { value: marlin_rate };
+        config.shark_spawn_rate = SpawnCurve::Constant { value: shark_rate };
+        config
+    }
+
+    #[test]
+    fn far_creatures_rest_in_a_persistent_world() {
+        let mut level = Level::new(&persistent_level(0.0, 0.0), 5);
+        let far = HexCoord::new(Level::ACTIVE_RADIUS + 1, -(Level::ACTIVE_RADIUS + 1), 0);
+        let near = HexCoord::new(3, -3, 0);
+        level.map.insert(far, HexCell { marlins: vec![Marlin::new()], sharks: vec![Shark::new()] });
+        level.map.entry(near).or_default().marlins.push(Marlin::new());
+        let mut near_moved = false;
+        for _ in 0..10 {
+            assert_eq!(level.advance(UserAction::Move(HexDir::ZERO)), Ok(None));
+            assert!(!level.events().iter().any(|e| matches!(e, GameEvent::MarlinMoved { from, .. } | GameEvent::SharkMoved { from, .. } if *from == far)));
+            near_moved |= level.events().iter().any(|e| matches!(e, GameEvent::MarlinMoved { from, .. } if *from != far));
+            assert_eq!(level.map[&far].marlins.len(), 1);
+            assert_eq!(level.map[&far].sharks.len(), 1);
+        }
+        // out of sight isn't the same as out of reach
+        assert!(near_moved);
+    }
+
+    #[test]
+    fn spawns_stop_at_the_region_cap() {
+        let config = persistent_level(3.0, 1.0);
+        let cap = config.world.unwrap().region_cap;
+        let mut level = Level::new(&config, 5);
+        let mut policy = GreedyHunter::new(5);
+        let mut capped = 0;
+        for _ in 0..200 {
+            if level.advance(policy.choose_action(&level.view())).unwrap().is_some() {
+                break;
+            }
+            // creatures may swim into a full region, but nothing spawns in one
+            let populations = level.region_populations();
+            for event in level.events() {
+                if let GameEvent::CreatureSpawned { coord, .. } = event {
+                    let population = populations[&config.world.unwrap().region_of(coord)];
+                    assert!(population <= cap, "{} creatures in the region of {:?}", population, coord);
+                    capped += (population == cap) as usize;
+                }
+            }
+        }
+        assert!(capped > 0, "no region filled up");
+    }
+
+    #[test]
+    fn replaying_a_level_only_pays_for_a_better_catch() {
+        let mut game = Game::with_campaign(&Campaign::default(), 11);
+        let pass = |game: &mut Game, level_index, surplus| {
+            let coins = game.get_coins();
+            game.record_pass(LevelScore { level_index, surplus, ..LevelScore::default() });
+            game.get_coins() - coins
+        };
+        assert_eq!(pass(&mut game, 0, 3), 30);
+        assert_eq!(pass(&mut game, 0, 3), 0);
+        assert_eq!(pass(&mut game, 0, 1), 0);
+        // the latest score counts, but coins are only paid beyond the best catch
+        assert_eq!(game.get_score_of(0).unwrap().surplus, 1);
+        assert_eq!(pass(&mut game, 0, 5), 20);
+        assert_eq!(pass(&mut game, 1, 2), 20);
+        game.restart_campaign();
+        assert_eq!(pass(&mut game, 0, 3), 30);
+    }
+
+    #[test]
+    fn refused_actions_say_why() {
+        let mut campaign = Campaign::default();
+        let reef = HexCoord::ZERO + HexDir::SOUTH;
+        campaign.levels[0].terrain = TerrainConfig { cells: vec![(reef, Terrain::Reef)], generator: None };
+        let mut game = Game::with_campaign(&campaign, 11);
+        let refuse = |game: &mut Game, action| {
+            let turn = game.get_turn();
+            assert_eq!(game.handle_action(action), InputResult::InvalidInput);
+            assert_eq!(game.get_turn(), turn);
+            game.last_error().unwrap()
+        };
+        // in the harbor
+        assert_eq!(refuse(&mut game, WasmUserAction::discover_action()), ActionError::InHarbor);
+        assert_eq!(refuse(&mut game, WasmUserAction::capture_action(HexCoord::ZERO)), ActionError::InHarbor);
+        assert_eq!(refuse(&mut game, WasmUserAction::attack_action(HexCoord::ZERO + HexDir::NORTH, 0)), ActionError::InHarbor);
+        assert_eq!(refuse(&mut game, WasmUserAction::move_action(reef)), ActionError::Blocked { terrain: Terrain::Reef });
+        assert_eq!(refuse(&mut game, WasmUserAction::move_action(HexCoord::new(2, -1, -1))), ActionError::NotAdjacent);
+        let price = game.equipment().next_price(Upgrade::Net).unwrap();
+        assert_eq!(refuse(&mut game, WasmUserAction::buy_action(Upgrade::Net)), ActionError::NotEnoughCoins { price, coins: 0 });
+        game.coins = 1000;
+        (0..Upgrade::Net.max_level()).for_each(|_| game.equipment.upgrade(Upgrade::Net));
+        assert_eq!(refuse(&mut game, WasmUserAction::buy_action(Upgrade::Net)), ActionError::FullyUpgraded { upgrade: Upgrade::Net });
+
+        // out at sea
+        assert_eq!(game.handle_action(WasmUserAction::move_action(HexCoord::ZERO + HexDir::NORTH)), InputResult::Ok);
+        assert_eq!(game.last_error(), None);
+        assert_eq!(refuse(&mut game, WasmUserAction::buy_action(Upgrade::Hull)), ActionError::NotInHarbor);
+        assert_eq!(refuse(&mut game, WasmUserAction::capture_action(HexCoord::new(2, -1, -1))), ActionError::OutOfRange { distance: 2, max: Fisherman::CAPTURE_RADIUS });
+        let far = HexCoord::new(3, 0, -3);
+        assert_eq!(refuse(&mut game, WasmUserAction::attack_action(far, 9)), ActionError::NoSuchShark { coord: far, index: 9 });
+        // a level on its own, as the simulator plays it
+        assert_eq!(game.current_level.advance(UserAction::Buy(Upgrade::Hull)), Err(ActionError::NoShop));
+    }
+
+    #[test]
+    fn retried_level_keeps_the_latest_score() {
+        let mut game = Game::with_campaign(&Campaign::default(), 11);
+        let first = LevelScore { level_index: 0, marlins: 3, hp_left: 5, turns: 20, ..LevelScore::default() };
+        let retried = LevelScore { level_index: 0, marlins: 3, hp_left: 1, turns: 40, ..LevelScore::default() };
+        game.record_pass(first);
+        let second = LevelScore { level_index: 1, marlins: 5, turns: 50, ..LevelScore::default() };
+        game.record_pass(second);
+        game.record_pass(retried);
+        // even when it is worse
+        assert_eq!(game.get_score_of(0), Some(retried));
+        assert_eq!(game.scores().len(), 2);
+        assert_eq!(game.get_score(), retried.total() + second.total());
+        assert!(retried.total() < first.total());
+    }
+
+    #[test]
+    fn buying_an_unknown_upgrade_is_refused() {
+        let mut game = Game::with_campaign(&Campaign::default(), 11);
+        let buy = WasmUserAction { action_type: WasmUserActionType::Buy, param_0: HexCoord::ZERO, param_1: Upgrade::ALL.len() };
+        assert_eq!(game.handle_action(buy), InputResult::InvalidInput);
+        assert_eq!(game.last_error(), Some(ActionError::NoSuchUpgrade { index: Upgrade::ALL.len() }));
+        assert!(game.get_replay().turns().is_empty());
+    }
+
+    #[test]
+    fn save_with_missing_levels_is_rejected() {
+        let game = Game::with_campaign(&Campaign::default(), 11);
+        let mut save: serde_json::Value = serde_json::from_str(&game.save()).unwrap();
+        save["paid_surplus"].as_array_mut().unwrap().pop();
+        assert_eq!(Game::load(&save.to_string()).err().unwrap(), "inconsistent save: 3 levels, but 3 attempt counts and 2 paid surpluses");
+    }
+
+    #[test]
+    fn harbors_stay_free_of_creatures() {
+        // the level with three harbors far apart, so the boat often sails past one it isn't docked at
+        let mut campaign = Campaign::from_json(include_str!("../campaigns/harbors.json")).unwrap();
+        campaign.levels.drain(..campaign.levels.len() - 1);
+        for seed in 0..5 {
+            let mut game = Game::with_campaign(&campaign, seed);
+            let mut policy = GreedyHunter::new(seed);
+            for _ in 0..300 {
+                if game.handle_action(policy.choose_action(&game.view()).into()) != InputResult::Ok {
+                    game.change_level(LevelChange::Retry).unwrap();
+                }
+                for harbor in game.current_level.terrain.harbors() {
+                    let cell = game.current_level.map.get(harbor);
+                    assert!(cell.is_none_or(|c| c.marlins.is_empty() && c.sharks.is_empty()), "creatures in harbor {:?}", harbor);
+                }
+            }
         }
     }
 }
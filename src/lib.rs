@@ -1,5 +1,23 @@
-pub(crate) mod map;
-pub(crate) mod entities;
+pub mod map;
+pub mod terrain;
+pub mod ocean;
+pub mod clock;
+pub mod weather;
+pub mod shop;
+pub mod entities;
 pub mod level;
+pub mod event;
+pub mod score;
+pub mod highscore;
+pub mod campaign;
+pub mod replay;
+pub mod sim;
+pub mod frame;
+pub mod render;
+pub mod driver;
+pub mod ui;
+pub mod policy;
 #[cfg(feature = "cli")]
-pub mod cli;
\ No newline at end of file
+pub mod cli;
+#[cfg(feature = "cli")]
+pub mod keymap;
\ No newline at end of file
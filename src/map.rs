@@ -1,12 +1,13 @@
 
-use std::{i32, ops::{Add, Mul, Sub}};
+use std::{cmp::Reverse, collections::{BinaryHeap, HashMap, VecDeque}, ops::{Add, Mul, Sub}};
 
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use wasm_bindgen::prelude::*;
 
 use crate::entities::{Marlin, Shark};
 
 #[wasm_bindgen]
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Serialize, Deserialize)]
 pub struct HexCoord {
     pub q: i32,
     pub r: i32,
@@ -15,6 +16,121 @@ pub struct HexCoord {
 
 impl HexCoord {
     pub const ZERO: Self = Self { q: 0, r: 0, s: 0 };
+
+    /// The 6 neighbors, in the order of `HexDir::ALL`.
+    pub fn neighbors(&self) -> [HexCoord; 6] {
+        HexDir::ALL.map(|d| *self + d)
+    }
+
+    /// Cells at exactly `radius`, walking clockwise from the one straight north.
+    pub fn ring(&self, radius: i32) -> impl Iterator<Item = HexCoord> {
+        let start = *self + HexDir::NORTH * radius;
+        // walk each of the 6 sides, `radius` steps each
+        let steps = if radius == 0 { 1 } else { 6 * radius };
+        (0..steps).scan(start, move |coord, i| {
+            let current = *coord;
+            if radius > 0 {
+                *coord = *coord + HexDir::from_index(i as usize / radius as usize + 2);
+            }
+            Some(current)
+        })
+    }
+
+    /// Cells within `radius`, ring by ring from the center outwards.
+    pub fn spiral(&self, radius: i32) -> impl Iterator<Item = HexCoord> {
+        let center = *self;
+        (0..=radius).flat_map(move |r| center.ring(r))
+    }
+
+    /// Cells on the straight line to `other`, both ends included.
+    pub fn line_to(&self, other: &HexCoord) -> Vec<HexCoord> {
+        let n = self.distance(other);
+        // nudge off the edges between cells, so ties always round the same way
+        let (q0, r0) = (self.q as f64 + 1e-6, self.r as f64 + 1e-6);
+        let (q1, r1) = (other.q as f64 + 1e-6, other.r as f64 + 1e-6);
+        (0..=n).map(|i| {
+            let t = if n == 0 { 0.0 } else { i as f64 / n as f64 };
+            Self::round(q0 + (q1 - q0) * t, r0 + (r1 - r0) * t)
+        }).collect()
+    }
+
+    fn round(q: f64, r: f64) -> HexCoord {
+        let s = -q - r;
+        let (mut rq, mut rr, mut rs) = (q.round(), r.round(), s.round());
+        let (dq, dr, ds) = ((rq - q).abs(), (rr - r).abs(), (rs - s).abs());
+        // fix the coordinate that was rounded the most, so q + r + s stays 0
+        if dq > dr && dq > ds {
+            rq = -rr - rs;
+        } else if dr > ds {
+            rr = -rq - rs;
+        } else {
+            rs = -rq - rr;
+        }
+        HexCoord { q: rq as i32, r: rr as i32, s: rs as i32 }
+    }
+
+    /// Whether nothing opaque lies strictly between the two cells.
+    pub fn has_line_of_sight(&self, other: &HexCoord, is_opaque: impl Fn(&HexCoord) -> bool) -> bool {
+        let line = self.line_to(other);
+        line.len() <= 2 || !line[1..line.len() - 1].iter().any(is_opaque)
+    }
+
+    /// Breadth-first flood fill: the number of steps to every cell reachable within `max_steps`.
+    /// The start is always included, `passable` decides for every other cell.
+    pub fn distances_within(&self, max_steps: i32, passable: impl Fn(&HexCoord) -> bool) -> HashMap<HexCoord, i32> {
+        let mut distances = HashMap::from([(*self, 0)]);
+        let mut queue = VecDeque::from([*self]);
+        while let Some(coord) = queue.pop_front() {
+            let steps = distances[&coord];
+            if steps >= max_steps {
+                continue;
+            }
+            for next in coord.neighbors() {
+                if !distances.contains_key(&next) && passable(&next) {
+                    distances.insert(next, steps + 1);
+                    queue.push_back(next);
+                }
+            }
+        }
+        distances
+    }
+
+    /// A* search for the cheapest path to `goal`, without the start and with the goal.
+    /// `cost` is the price of stepping between two adjacent cells and must be at least 1,
+    /// paths costing more than `max_cost` are given up on, so an unreachable goal doesn't search the whole ocean.
+    pub fn find_path(&self, goal: &HexCoord, max_cost: u32, passable: impl Fn(&HexCoord) -> bool, cost: impl Fn(&HexCoord, &HexCoord) -> u32) -> Option<Vec<HexCoord>> {
+        let mut came_from = HashMap::new();
+        let mut best = HashMap::from([(*self, 0u32)]);
+        // ties are broken by coordinate, so the same path is found every run
+        let mut open = BinaryHeap::from([Reverse((self.distance(goal) as u32, 0u32, *self))]);
+        while let Some(Reverse((_, g, coord))) = open.pop() {
+            if coord == *goal {
+                let mut path = vec![coord];
+                while let Some(prev) = came_from.get(path.last().unwrap()) {
+                    path.push(*prev);
+                }
+                path.pop();
+                path.reverse();
+                return Some(path);
+            }
+            if g > best[&coord] {
+                continue;
+            }
+            for next in coord.neighbors() {
+                if !passable(&next) {
+                    continue;
+                }
+                let next_g = g + cost(&coord, &next).max(1);
+                if next_g > max_cost || best.get(&next).is_some_and(|b| *b <= next_g) {
+                    continue;
+                }
+                best.insert(next, next_g);
+                came_from.insert(next, coord);
+                open.push(Reverse((next_g + next.distance(goal) as u32, next_g, next)));
+            }
+        }
+        None
+    }
 }
 #[wasm_bindgen]
 impl HexCoord {
@@ -70,10 +186,15 @@ impl HexCoord {
     pub fn distance(&self, other: &HexCoord) -> i32 {
         ((self.q - other.q).abs() + (self.r - other.r).abs() + (self.s - other.s).abs()) / 2
     }
+
+    /// The adjacent cell in direction `HexDir::from_index(index)`.
+    pub fn neighbor(&self, index: usize) -> HexCoord {
+        *self + HexDir::from_index(index)
+    }
     // Create a new HexCell
 }
 #[wasm_bindgen]
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct HexDir {
     pub q: i32,
     pub r: i32,
@@ -89,6 +210,8 @@ impl HexDir {
     pub const SOUTHWEST: HexDir = HexDir { q: -1, r: 0, s: 1 };
     pub const NORTHWEST: HexDir = HexDir { q: -1, r: 1, s: 0 };
 
+    /// The 6 unit directions, clockwise from north.
+    pub const ALL: [HexDir; 6] = [Self::NORTH, Self::NORTHEAST, Self::SOUTHEAST, Self::SOUTH, Self::SOUTHWEST, Self::NORTHWEST];
 }
 #[wasm_bindgen]
 impl HexDir {
@@ -99,10 +222,66 @@ impl HexDir {
         Self {q, r, s}
     }
     
+    /// # Safety
+    /// `q + r + s` must be 0, this is only checked in debug builds.
     pub unsafe fn new_unchecked(q: i32, r: i32, s: i32) -> Self {
         debug_assert!(q + r + s == 0);
         Self {q, r, s}
     }
+
+    /// Unit direction by index, clockwise from north, wrapping around every 6.
+    pub fn from_index(index: usize) -> HexDir {
+        Self::ALL[index % 6]
+    }
+
+    /// Position in `HexDir::ALL`, `None` if this isn't a unit direction.
+    pub fn index(&self) -> Option<usize> {
+        Self::ALL.iter().position(|d| d == self)
+    }
+
+    /// Rotated by 60° clockwise `steps` times, counterclockwise if negative.
+    pub fn rotate(&self, steps: i32) -> HexDir {
+        let mut dir = *self;
+        for _ in 0..steps.rem_euclid(6) {
+            dir = HexDir { q: -dir.s, r: -dir.q, s: -dir.r };
+        }
+        dir
+    }
+
+    /// Mirror image keeping q: north and south swap, so do northeast and southeast.
+    pub fn reflect_q(&self) -> HexDir {
+        HexDir { q: self.q, r: self.s, s: self.r }
+    }
+
+    /// Mirror image keeping r: north and northwest swap, so do northeast and southwest.
+    pub fn reflect_r(&self) -> HexDir {
+        HexDir { q: self.s, r: self.r, s: self.q }
+    }
+
+    /// Mirror image keeping s: north and northeast swap, so do northwest and southeast.
+    pub fn reflect_s(&self) -> HexDir {
+        HexDir { q: self.r, r: self.q, s: self.s }
+    }
+
+    #[inline]
+    pub fn opposite(&self) -> HexDir {
+        *self * -1
+    }
+}
+
+impl std::fmt::Display for HexDir {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match *self {
+            HexDir::ZERO => write!(f, "nowhere"),
+            HexDir::NORTH => write!(f, "north"),
+            HexDir::NORTHEAST => write!(f, "northeast"),
+            HexDir::SOUTHEAST => write!(f, "southeast"),
+            HexDir::SOUTH => write!(f, "south"),
+            HexDir::SOUTHWEST => write!(f, "southwest"),
+            HexDir::NORTHWEST => write!(f, "northwest"),
+            HexDir { q, r, s } => write!(f, "({}, {}, {})", q, r, s),
+        }
+    }
 }
 
 impl Add for HexDir {
@@ -176,9 +355,153 @@ impl Sub<HexCoord> for HexCoord {
 }
 
 
-#[derive(Debug, Default, Clone)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct HexCell {
     pub marlins: Vec<Marlin>,
     pub sharks: Vec<Shark>,
 }
 
+/// (De)serializes a map keyed by coordinates as a list of `(coord, value)` entries, since JSON objects only allow string keys.
+pub mod hex_map_entries {
+    use super::*;
+
+    pub fn serialize<S: Serializer, V: Serialize>(map: &HashMap<HexCoord, V>, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut entries = map.iter().collect::<Vec<_>>();
+        entries.sort_by_key(|(coord, _)| **coord);
+        serializer.collect_seq(entries)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>, V: Deserialize<'de>>(deserializer: D) -> Result<HashMap<HexCoord, V>, D::Error> {
+        Ok(Vec::<(HexCoord, V)>::deserialize(deserializer)?.into_iter().collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // a reef wall just east of the origin, from r = -3 to r = 2
+    fn wall() -> Vec<HexCoord> {
+        (-3..=2).map(|r| HexCoord::new(1, r, -1 - r)).collect()
+    }
+
+    fn path_cost(start: HexCoord, path: &[HexCoord], cost: impl Fn(&HexCoord, &HexCoord) -> u32) -> u32 {
+        let mut from = start;
+        path.iter().map(|to| {
+            assert_eq!(from.distance(to), 1, "path jumps from {:?} to {:?}", from, to);
+            let c = cost(&from, to);
+            from = *to;
+            c
+        }).sum()
+    }
+
+    #[test]
+    fn path_goes_around_a_wall() {
+        let wall = wall();
+        let goal = HexCoord::new(2, -1, -1);
+        let passable = |c: &HexCoord| !wall.contains(c);
+        let path = HexCoord::ZERO.find_path(&goal, 20, passable, |_, _| 1).unwrap();
+        assert_eq!(path.last(), Some(&goal));
+        assert!(path.iter().all(passable));
+        path_cost(HexCoord::ZERO, &path, |_, _| 1);
+        // as short as the flood fill says, and longer than the straight line
+        let steps = HexCoord::ZERO.distances_within(20, passable)[&goal];
+        assert_eq!(path.len() as i32, steps);
+        assert!(steps > HexCoord::ZERO.distance(&goal));
+    }
+
+    #[test]
+    fn path_gives_up_on_unreachable_goals() {
+        let goal = HexCoord::new(3, 0, -3);
+        let walled_in = goal.neighbors();
+        assert_eq!(HexCoord::ZERO.find_path(&goal, 100, |c| !walled_in.contains(c), |_, _| 1), None);
+        assert_eq!(HexCoord::ZERO.find_path(&goal, 2, |_| true, |_, _| 1), None);
+        assert_eq!(HexCoord::ZERO.find_path(&goal, 3, |_| true, |_, _| 1).map(|p| p.len()), Some(3));
+        assert_eq!(HexCoord::ZERO.find_path(&HexCoord::ZERO, 0, |_| true, |_, _| 1), Some(vec![]));
+    }
+
+    #[test]
+    fn path_avoids_costly_cells() {
+        // a strong current against the boat on the two cells straight north
+        let current = [HexCoord::new(0, 1, -1), HexCoord::new(0, 2, -2)];
+        let cost = |_: &HexCoord, to: &HexCoord| if current.contains(to) { 5 } else { 1 };
+        let goal = HexCoord::new(0, 3, -3);
+        let path = HexCoord::ZERO.find_path(&goal, 20, |_| true, cost).unwrap();
+        assert_eq!(path_cost(HexCoord::ZERO, &path, cost), 4);
+        assert!(!path.iter().any(|c| current.contains(c)));
+        // too expensive either way
+        assert_eq!(HexCoord::ZERO.find_path(&goal, 3, |_| true, cost), None);
+    }
+
+    #[test]
+    fn flood_fill_counts_steps_around_walls() {
+        let wall = wall();
+        let distances = HexCoord::ZERO.distances_within(3, |c| !wall.contains(c));
+        assert_eq!(distances[&HexCoord::ZERO], 0);
+        assert!(wall.iter().all(|c| !distances.contains_key(c)));
+        assert!(distances.values().all(|d| *d <= 3));
+        assert_eq!(distances.get(&HexCoord::new(2, -1, -1)), None);
+        assert_eq!(distances[&HexCoord::new(-3, 0, 3)], 3);
+    }
+
+    #[test]
+    fn rings_and_spirals() {
+        let center = HexCoord::new(2, -5, 3);
+        for r in 0..6 {
+            let ring = center.ring(r).collect::<Vec<_>>();
+            assert_eq!(ring.len() as i32, if r == 0 { 1 } else { 6 * r });
+            assert!(ring.iter().all(|c| c.distance(&center) == r));
+            let mut unique = ring.clone();
+            unique.sort();
+            unique.dedup();
+            assert_eq!(unique.len(), ring.len());
+
+            let spiral = center.spiral(r).collect::<Vec<_>>();
+            assert_eq!(spiral.len() as i32, 3 * r * (r + 1) + 1);
+            let mut unique = spiral.clone();
+            unique.sort();
+            unique.dedup();
+            assert_eq!(unique.len(), spiral.len());
+            assert!(spiral.iter().all(|c| c.distance(&center) <= r));
+        }
+    }
+
+    #[test]
+    fn lines_step_one_hex_at_a_time() {
+        let start = HexCoord::new(-2, 1, 1);
+        for end in HexCoord::ZERO.spiral(5) {
+            let line = start.line_to(&end);
+            assert_eq!(line.len() as i32, start.distance(&end) + 1);
+            assert_eq!(line.first(), Some(&start));
+            assert_eq!(line.last(), Some(&end));
+            assert!(line.windows(2).all(|w| w[0].distance(&w[1]) == 1), "{:?}", line);
+        }
+    }
+
+    #[test]
+    fn line_of_sight_ignores_the_ends() {
+        let end = HexCoord::new(0, 4, -4);
+        assert!(HexCoord::ZERO.has_line_of_sight(&end, |c| *c == end || *c == HexCoord::ZERO));
+        assert!(!HexCoord::ZERO.has_line_of_sight(&end, |c| *c == HexCoord::new(0, 2, -2)));
+        assert!(HexCoord::ZERO.has_line_of_sight(&HexCoord::new(0, 1, -1), |_| true));
+    }
+
+    #[test]
+    fn direction_symmetries() {
+        for (i, dir) in HexDir::ALL.iter().enumerate() {
+            assert_eq!(dir.rotate(6), *dir);
+            assert_eq!(dir.rotate(0), *dir);
+            assert_eq!(dir.rotate(1), HexDir::from_index(i + 1));
+            assert_eq!(dir.rotate(-1).rotate(1), *dir);
+            assert_eq!(dir.rotate(3), dir.opposite());
+            assert_eq!(*dir + dir.opposite(), HexDir::ZERO);
+            assert_eq!(dir.reflect_q().reflect_q(), *dir);
+            assert_eq!(dir.reflect_r().reflect_r(), *dir);
+            assert_eq!(dir.reflect_s().reflect_s(), *dir);
+            assert!(dir.reflect_q().index().is_some());
+        }
+        assert_eq!(HexDir::NORTH.reflect_q(), HexDir::SOUTH);
+        assert_eq!(HexDir::NORTH.reflect_r(), HexDir::NORTHWEST);
+        assert_eq!(HexDir::NORTH.reflect_s(), HexDir::NORTHEAST);
+    }
+}
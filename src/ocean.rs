@@ -0,0 +1,154 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{campaign::{NoiseOcean, OceanGenerator}, map::HexCoord, terrain::hash2};
+
+/// Where creatures like to spawn: multiplies the spawn curves of a level, cell by cell.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Ocean {
+    generator: OceanGenerator,
+    seed: u64,
+}
+
+impl Ocean {
+    // every field gets its own noise, from the level seed and a salt
+    const DEPTH_SALT: u64 = 0x6465707468;
+    const RICHNESS_SALT: u64 = 0x726963686e657373;
+    const SHARK_SALT: u64 = 0x736861726b;
+
+    pub fn new(generator: &OceanGenerator, seed: u64) -> Self {
+        Self { generator: generator.clone(), seed }
+    }
+
+    /// How deep the ocean is at `coord`, from 0 to 1. Flat 0.5 without a noise generator.
+    pub fn depth(&self, coord: &HexCoord) -> f32 {
+        self.field(Self::DEPTH_SALT, coord)
+    }
+
+    /// How much fish food there is at `coord`, from 0 to 1.
+    pub fn richness(&self, coord: &HexCoord) -> f32 {
+        self.field(Self::RICHNESS_SALT, coord)
+    }
+
+    /// How much sharks like `coord`, from 0 to 1.
+    pub fn sharkiness(&self, coord: &HexCoord) -> f32 {
+        self.field(Self::SHARK_SALT, coord)
+    }
+
+    pub fn is_trench(&self, coord: &HexCoord) -> bool {
+        match &self.generator {
+            OceanGenerator::Uniform => false,
+            OceanGenerator::Noise(noise) => self.depth(coord) > noise.trench_level,
+        }
+    }
+
+    pub fn is_hot_spot(&self, coord: &HexCoord) -> bool {
+        match &self.generator {
+            OceanGenerator::Uniform => false,
+            OceanGenerator::Noise(noise) => self.richness(coord) > noise.hot_spot_level,
+        }
+    }
+
+    pub fn is_shark_zone(&self, coord: &HexCoord) -> bool {
+        match &self.generator {
+            OceanGenerator::Uniform => false,
+            OceanGenerator::Noise(noise) => self.sharkiness(coord) > noise.shark_zone_level || self.is_trench(coord),
+        }
+    }
+
+    /// Multiplier of the marlin spawn rate at `coord`: none in trenches, more in hot spots.
+    pub fn marlin_factor(&self, coord: &HexCoord) -> f32 {
+        match &self.generator {
+            OceanGenerator::Uniform => 1.0,
+            OceanGenerator::Noise(_) if self.is_trench(coord) => 0.0,
+            OceanGenerator::Noise(noise) if self.is_hot_spot(coord) => noise.hot_spot_boost,
+            OceanGenerator::Noise(_) => 1.0,
+        }
+    }
+
+    /// Multiplier of the shark spawn rate at `coord`: more in shark zones and trenches.
+    pub fn shark_factor(&self, coord: &HexCoord) -> f32 {
+        match &self.generator {
+            OceanGenerator::Uniform => 1.0,
+            OceanGenerator::Noise(noise) if self.is_shark_zone(coord) => noise.shark_zone_boost,
+            OceanGenerator::Noise(_) => 1.0,
+        }
+    }
+
+    fn field(&self, salt: u64, coord: &HexCoord) -> f32 {
+        match &self.generator {
+            OceanGenerator::Uniform => 0.5,
+            OceanGenerator::Noise(noise) => fractal_noise(self.seed ^ salt, noise, coord),
+        }
+    }
+}
+
+// value noise summed over octaves, each twice as fine and half as strong as the previous one, scaled to [0, 1]
+fn fractal_noise(seed: u64, noise: &NoiseOcean, coord: &HexCoord) -> f32 {
+    // axial to cartesian, so features are round rather than skewed
+    let x = coord.q as f32 * 3f32.sqrt() / 2.0;
+    let y = coord.r as f32 + coord.q as f32 / 2.0;
+    let mut total = 0.0;
+    let mut amplitude = 1.0;
+    let mut max = 0.0;
+    let mut frequency = 1.0 / noise.scale;
+    for octave in 0..noise.octaves {
+        total += amplitude * value_noise(seed.wrapping_add(octave as u64), x * frequency, y * frequency);
+        max += amplitude;
+        amplitude /= 2.0;
+        frequency *= 2.0;
+    }
+    total / max
+}
+
+// random values on the integer lattice, smoothly interpolated in between
+fn value_noise(seed: u64, x: f32, y: f32) -> f32 {
+    let (x0, y0) = (x.floor(), y.floor());
+    let (ix, iy) = (x0 as i32, y0 as i32);
+    let smooth = |t: f32| t * t * (3.0 - 2.0 * t);
+    let (tx, ty) = (smooth(x - x0), smooth(y - y0));
+    let corner = |dx: i32, dy: i32| (hash2(seed, ix + dx, iy + dy) >> 40) as f32 / (1u64 << 24) as f32;
+    let top = corner(0, 0) + (corner(1, 0) - corner(0, 0)) * tx;
+    let bottom = corner(0, 1) + (corner(1, 1) - corner(0, 1)) * tx;
+    top + (bottom - top) * ty
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn noise() -> OceanGenerator {
+        OceanGenerator::Noise(NoiseOcean { scale: 6.0, octaves: 3, hot_spot_level: 0.6, hot_spot_boost: 3.0, trench_level: 0.7, shark_zone_level: 0.65, shark_zone_boost: 2.0 })
+    }
+
+    // every field and factor of the cells around the origin
+    fn survey(ocean: &Ocean) -> Vec<(f32, f32, f32, f32, f32)> {
+        HexCoord::ZERO.within_radius(12).iter().map(|c| (ocean.depth(c), ocean.richness(c), ocean.sharkiness(c), ocean.marlin_factor(c), ocean.shark_factor(c))).collect()
+    }
+
+    #[test]
+    fn same_seed_same_ocean() {
+        for seed in [0, 1, 42, u64::MAX] {
+            assert_eq!(survey(&Ocean::new(&noise(), seed)), survey(&Ocean::new(&noise(), seed)), "seed {}", seed);
+        }
+    }
+
+    #[test]
+    fn different_seeds_different_oceans() {
+        let oceans = (0..5).map(|seed| survey(&Ocean::new(&noise(), seed))).collect::<Vec<_>>();
+        for (i, a) in oceans.iter().enumerate() {
+            for b in &oceans[i + 1..] {
+                assert_ne!(a, b);
+            }
+        }
+        // but a uniform ocean is the same everywhere
+        assert_eq!(survey(&Ocean::new(&OceanGenerator::Uniform, 1)), survey(&Ocean::new(&OceanGenerator::Uniform, 2)));
+    }
+
+    #[test]
+    fn fields_stay_in_range() {
+        let ocean = Ocean::new(&noise(), 7);
+        for (depth, richness, sharkiness, _, _) in survey(&ocean) {
+            assert!([depth, richness, sharkiness].iter().all(|v| (0.0..=1.0).contains(v)));
+        }
+    }
+}
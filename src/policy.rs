@@ -0,0 +1,259 @@
+use std::collections::HashMap;
+
+use rand::{seq::SliceRandom, Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use crate::{clock::TimeOfDay, weather::WeatherKind, entities::{Fisherman, Shark}, level::UserAction, map::{HexCell, HexCoord, HexDir}, terrain::{Terrain, TerrainMap}};
+
+/// What the player can see of a level: the fisherman, the target, and the cells in sight.
+/// Undiscovered marlins are not visible, so a policy can't cheat.
+pub struct LevelView<'a> {
+    fisherman: &'a Fisherman,
+    target: usize,
+    map: &'a HashMap<HexCoord, HexCell>,
+    terrain: &'a TerrainMap,
+    time: TimeOfDay,
+    weather: WeatherKind,
+    turns_left: Option<usize>,
+}
+
+impl<'a> LevelView<'a> {
+    pub(crate) fn new(fisherman: &'a Fisherman, target: usize, map: &'a HashMap<HexCoord, HexCell>, terrain: &'a TerrainMap, time: TimeOfDay, weather: WeatherKind, turns_left: Option<usize>) -> Self {
+        Self { fisherman, target, map, terrain, time, weather, turns_left }
+    }
+
+    #[inline]
+    pub fn time_of_day(&self) -> TimeOfDay {
+        self.time
+    }
+
+    #[inline]
+    pub fn weather(&self) -> WeatherKind {
+        self.weather
+    }
+
+    /// Turns left before the deadline, if the level has one.
+    #[inline]
+    pub fn turns_left(&self) -> Option<usize> {
+        self.turns_left
+    }
+
+    /// How far sharks spot the boat from right now.
+    pub fn shark_sight(&self) -> i32 {
+        Shark::visual_radius(self.time)
+    }
+
+    #[inline]
+    pub fn fisherman(&self) -> &Fisherman {
+        self.fisherman
+    }
+
+    #[inline]
+    pub fn target(&self) -> usize {
+        self.target
+    }
+
+    /// Terrain is charted, so it is known even out of sight.
+    pub fn terrain_at(&self, coord: &HexCoord) -> Terrain {
+        self.terrain.at(coord)
+    }
+
+    pub fn is_harbor(&self, coord: &HexCoord) -> bool {
+        self.terrain.is_harbor(coord)
+    }
+
+    pub fn nearest_harbor(&self) -> HexCoord {
+        self.terrain.nearest_harbor(&self.fisherman.get_coord())
+    }
+
+    pub fn is_visible(&self, coord: &HexCoord) -> bool {
+        self.fisherman.get_coord().distance(coord) <= self.fisherman.visual_radius(self.time, self.weather)
+    }
+
+    pub fn visible_coords(&self) -> Vec<HexCoord> {
+        self.fisherman.get_coord().within_radius(self.fisherman.visual_radius(self.time, self.weather))
+    }
+
+    pub fn discovered_marlins_at(&self, coord: &HexCoord) -> usize {
+        if !self.is_visible(coord) {
+            return 0;
+        }
+        self.map.get(coord).map_or(0, |c| c.marlins.iter().filter(|m| m.is_discovered()).count())
+    }
+
+    pub fn sharks_at(&self, coord: &HexCoord) -> usize {
+        if !self.is_visible(coord) {
+            return 0;
+        }
+        self.map.get(coord).map_or(0, |c| c.sharks.len())
+    }
+
+    pub fn shark_coords(&self) -> Vec<HexCoord> {
+        self.visible_coords().into_iter().filter(|c| self.sharks_at(c) > 0).collect()
+    }
+}
+
+/// An automated player, used by the simulator and for hints in the UI.
+pub trait Policy {
+    fn choose_action(&mut self, view: &LevelView) -> UserAction;
+}
+
+/// Looks up one of the built-in policies by its command line name.
+pub fn policy_by_name(name: &str, seed: u64) -> Option<Box<dyn Policy>> {
+    match name {
+        "random" => Some(Box::new(RandomWalker::new(seed))),
+        "greedy" => Some(Box::new(GreedyHunter::new(seed))),
+        "cautious" => Some(Box::new(CautiousHunter::new(seed))),
+        _ => None,
+    }
+}
+
+pub const POLICY_NAMES: [&str; 3] = ["random", "greedy", "cautious"];
+
+// stepping onto a current is expensive, since it may well carry the boat back
+const CURRENT_COST: u32 = 10;
+
+// the first step of the cheapest way around reefs and currents to `to`, or the open neighbor closest to it if there is none
+fn step_towards(view: &LevelView, from: HexCoord, to: HexCoord) -> HexDir {
+    if from == to {
+        return HexDir::ZERO;
+    }
+    let passable = |c: &HexCoord| view.terrain_at(c).boat_can_enter();
+    let cost = |_: &HexCoord, c: &HexCoord| if matches!(view.terrain_at(c), Terrain::Current(_)) { CURRENT_COST } else { 1 };
+    // allow generous detours, but don't search the whole ocean for an enclosed cell
+    let max_cost = from.distance(&to) as u32 * 3 + 2 * CURRENT_COST;
+    if let Some(path) = from.find_path(&to, max_cost, passable, cost) {
+        return path[0] - from;
+    }
+    HexDir::ALL.iter().copied()
+        .filter(|d| passable(&(from + *d)))
+        .min_by_key(|d| (from + *d).distance(&to))
+        .unwrap_or(HexDir::ZERO)
+}
+
+/// Moves to a random adjacent cell every turn.
+pub struct RandomWalker {
+    rng: ChaCha8Rng,
+}
+
+impl RandomWalker {
+    pub fn new(seed: u64) -> Self {
+        Self { rng: ChaCha8Rng::seed_from_u64(seed) }
+    }
+}
+
+impl Policy for RandomWalker {
+    fn choose_action(&mut self, view: &LevelView) -> UserAction {
+        let here = view.fisherman().get_coord();
+        let open = HexDir::ALL.iter().copied().filter(|d| view.terrain_at(&(here + *d)).boat_can_enter()).collect::<Vec<_>>();
+        UserAction::Move(*open.choose(&mut self.rng).unwrap_or(&HexDir::ZERO))
+    }
+}
+
+/// Discovers, chases and captures the closest marlins, and sails home once the target is met. Ignores sharks.
+pub struct GreedyHunter {
+    rng: ChaCha8Rng,
+    heading: HexDir,
+    // discovering twice in a row without moving finds nothing new
+    just_discovered: bool,
+}
+
+impl GreedyHunter {
+    // chance of picking a new heading each turn while wandering
+    const TURN_PROBABILITY: f32 = 0.2;
+
+    pub fn new(seed: u64) -> Self {
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        let heading = *HexDir::ALL.choose(&mut rng).unwrap();
+        Self { rng, heading, just_discovered: false }
+    }
+
+    fn wander(&mut self, view: &LevelView) -> UserAction {
+        let here = view.fisherman().get_coord();
+        let blocked = |d: &HexDir| !view.terrain_at(&(here + *d)).boat_can_enter();
+        if self.rng.gen::<f32>() < Self::TURN_PROBABILITY || blocked(&self.heading) {
+            let open = HexDir::ALL.iter().copied().filter(|d| !blocked(d)).collect::<Vec<_>>();
+            self.heading = *open.choose(&mut self.rng).unwrap_or(&HexDir::ZERO);
+        }
+        UserAction::Move(self.heading)
+    }
+
+    fn hunt(&mut self, view: &LevelView) -> UserAction {
+        let here = view.fisherman().get_coord();
+        if view.fisherman().get_captured_marlins() >= view.target() {
+            return UserAction::Move(step_towards(view, here, view.nearest_harbor()));
+        }
+        if view.is_harbor(&here) {
+            return self.wander(view);
+        }
+        // capture in the most crowded cell within reach
+        let in_reach = here.within_radius(Fisherman::CAPTURE_RADIUS).into_iter()
+            .map(|c| (view.discovered_marlins_at(&c), c))
+            .filter(|(n, _)| *n > 0)
+            .max_by_key(|(n, c)| (*n, *c));
+        if let Some((_, coord)) = in_reach {
+            return UserAction::Capture(coord - here);
+        }
+        let closest = view.visible_coords().into_iter()
+            .filter(|c| view.discovered_marlins_at(c) > 0)
+            .min_by_key(|c| (here.distance(c), *c));
+        if let Some(coord) = closest {
+            return UserAction::Move(step_towards(view, here, coord));
+        }
+        if !self.just_discovered {
+            return UserAction::Discover;
+        }
+        self.wander(view)
+    }
+}
+
+impl Policy for GreedyHunter {
+    fn choose_action(&mut self, view: &LevelView) -> UserAction {
+        let action = self.hunt(view);
+        self.just_discovered = matches!(action, UserAction::Discover);
+        action
+    }
+}
+
+/// Hunts like `GreedyHunter`, but never moves within sight of a shark if it can avoid it,
+/// and fights back when a shark reaches the boat.
+pub struct CautiousHunter {
+    hunter: GreedyHunter,
+}
+
+impl CautiousHunter {
+    pub fn new(seed: u64) -> Self {
+        Self { hunter: GreedyHunter::new(seed) }
+    }
+}
+
+impl Policy for CautiousHunter {
+    fn choose_action(&mut self, view: &LevelView) -> UserAction {
+        let here = view.fisherman().get_coord();
+        if !view.is_harbor(&here) && view.sharks_at(&here) > 0 {
+            return UserAction::Attack(here, 0);
+        }
+        let action = self.hunter.choose_action(view);
+        let UserAction::Move(dir) = action else {
+            return action;
+        };
+        let sharks = view.shark_coords();
+        // the harbor is always safe
+        let shark_distance = |c: &HexCoord| if view.is_harbor(c) {
+            i32::MAX
+        } else {
+            sharks.iter().map(|s| s.distance(c)).min().unwrap_or(i32::MAX)
+        };
+        let intended = here + dir;
+        if shark_distance(&intended) > view.shark_sight() {
+            return action;
+        }
+        let neighbors = here.within_radius(Fisherman::MOVE_RADIUS).into_iter().filter(|c| view.terrain_at(c).boat_can_enter()).collect::<Vec<_>>();
+        // the safe cell closest to where the hunter wanted to go, otherwise as far from sharks as possible
+        let safe = neighbors.iter()
+            .filter(|c| shark_distance(c) > view.shark_sight())
+            .min_by_key(|c| (c.distance(&intended), **c));
+        let target = safe.unwrap_or_else(|| neighbors.iter().max_by_key(|c| (shark_distance(c), **c)).unwrap());
+        UserAction::Move(*target - here)
+    }
+}
@@ -0,0 +1,604 @@
+use core::f32;
+use std::fmt::{self, Write};
+
+use crate::{clock::TimeOfDay, entities::{Fisherman, Shark}, frame::{Color, Frame, Style}, level::Game, map::{HexCoord, HexDir}, terrain::TerrainType};
+
+/// Draws the board around the boat, this turn's notable events and the status line into `f`.
+/// The attack target, if there is one, is highlighted.
+pub fn draw_game(f: &mut Frame, game: &Game, attack_target: Option<HexCoord>) -> fmt::Result {
+    let target = game.get_target();
+    let fisherman = game.get_fisherman();
+    draw_map(f, game, &fisherman, attack_target)?;
+    draw_legend(f)?;
+    for event in game.events().iter().filter(|e| e.is_notable()) {
+        writeln!(f, "{}", event)?;
+    }
+    let captured = if fisherman.get_captured_marlins() >= target { Style::fg(Color::Green) } else { Style::PLAIN };
+    f.styled(captured, format_args!("target: {}/{target}", fisherman.get_captured_marlins()))?;
+    write!(f, ", {}, HP: ", render_compass(game, &fisherman))?;
+    let mut hearts = String::new();
+    for _ in 0..fisherman.get_hp() {
+        hearts += "♥ "
+    }
+    for _ in fisherman.get_hp()..fisherman.get_initial_hp() {
+        hearts += "♡ "
+    }
+    let hp = if fisherman.get_hp() as f32 / fisherman.get_initial_hp() as f32 <= 0.25 { Style::fg(Color::Red) } else { Style::PLAIN };
+    f.styled(hp, hearts)?;
+    write!(f, ", ")?;
+    draw_clock(f, game, &fisherman)?;
+    write!(f, ", {}, {} coins ", render_weather(game), game.get_coins())
+}
+
+/// The board as plain text, for snapshot tests and bug reports. Marks stand in for the colors.
+pub fn render_game(game: &Game) -> String {
+    let mut frame = Frame::monochrome();
+    draw_game(&mut frame, game, None).unwrap();
+    frame.to_string()
+}
+
+// the hexes within sight of the boat, centered on it
+fn draw_map(f: &mut Frame, game: &Game, fisherman: &Fisherman, target: Option<HexCoord>) -> fmt::Result {
+    // first line
+    const N_US: usize = 6;
+    const N_SLASH: usize = 2;
+    let order: usize = Fisherman::VISUAL_RADIUS as usize;
+    let board = Board::new(game, fisherman, target);
+
+    // let order = 0;
+    // beginning
+    for _ in 0..(order * (N_US + N_SLASH) + N_SLASH) { write!(f, " ")?; }
+    for _ in 0..N_US { write!(f, "_")?; }
+    writeln!(f)?;
+    for i in 0..order {
+        for j in 0..(N_SLASH - 1) {
+            
+            for _ in 0..((order - i) * (N_US + N_SLASH) + N_SLASH - j - 1) { write!(f, " ")?; }
+            for k in 0..=i {
+                let q0 = -(i as i32) + 2 * (k as i32);
+                let q1 = -(i as i32) + 2 * (k as i32) + 1;
+                let r = (order as i32) - (k as i32);
+                let s0: i32 = (i as i32) - (order as i32) - (k as i32);
+                let s1 = (i as i32) - (order as i32) - (k as i32) - 1;
+                let coord0 = fisherman.get_coord() + unsafe { HexDir::new_unchecked(q0, r, s0) };
+                let coord1 = fisherman.get_coord() + unsafe { HexDir::new_unchecked(q1, r, s1) };
+                write!(f, "/")?;
+                // 0th line: marlins
+                board.marlins(f, coord0)?;
+                for _ in 0..(N_US - 6 + 2 * j) { write!(f, " ")?; }
+                write!(f, "\\")?;
+                // 2nd line: shark
+                if k != i {
+                    board.sharks(f, coord1)?;
+                    for _ in 0..(N_US + 2 * (N_SLASH - j - 1) - 8) { write!(f, " ")?; }
+                }
+            }
+            writeln!(f)?;
+        }
+
+        for _ in 0..((order - i - 1) * (N_US + N_SLASH) + N_SLASH) { write!(f, " ")?; }
+        for _ in 0..N_US { write!(f, "_")?; }
+        for k in 0..=i {
+            let q = -(i as i32) + 2 * (k as i32);
+            let r = (order as i32) - (k as i32);
+            let s = (i as i32) - (order as i32) - (k as i32);
+            let coord = fisherman.get_coord() + unsafe { HexDir::new_unchecked(q, r, s) };
+            write!(f, "/")?;
+
+            board.center(f, coord)?;
+            write!(f, "\\")?;
+            for _ in 0..N_US { write!(f, "_")?; }
+        }
+        writeln!(f)?;
+    }
+    // intermediate section
+    for i in 0..=order {
+        for j in 0..(N_SLASH - 1) {
+            for _ in 0..(N_SLASH - j - 1) { write!(f, " ")?; }
+            for k in 0..=order {
+
+                let q0 = -(order as i32) + 2 * (k as i32);
+                let q1 = -(order as i32) + 2 * (k as i32) + 1;
+                let r = (order as i32) - (i as i32) - (k as i32);
+                let s0: i32 = - (k as i32) + (i as i32);
+                let s1 = - (k as i32) + (i as i32) - 1;
+                let coord0 = fisherman.get_coord() + unsafe { HexDir::new_unchecked(q0, r, s0) };
+                let coord1 = fisherman.get_coord() + unsafe { HexDir::new_unchecked(q1, r, s1) };
+                write!(f, "/")?;
+
+                // 0th line: marlins
+                board.marlins(f, coord0)?;
+                for _ in 0..(N_US - 6 + 2 * j) { write!(f, " ")?; }
+                write!(f, "\\")?;
+
+                // 2nd line: shark
+                if k != order {
+                    board.sharks(f, coord1)?;
+                    for _ in 0..(N_US + 2 * (N_SLASH - j - 1) - 8) { write!(f, " ")?; }
+                }
+            }
+            writeln!(f)?;
+        }
+        for k in 0..=order {
+
+            let q = -(order as i32) + 2 * (k as i32);
+            let r = (order as i32) - (i as i32) - (k as i32);
+            let s: i32 = - (k as i32) + (i as i32);
+            let coord = fisherman.get_coord() + unsafe { HexDir::new_unchecked(q, r, s) };
+            write!(f, "/")?;
+            board.center(f, coord)?;
+            write!(f, "\\")?;
+            if k != order {
+                for _ in 0..N_US { write!(f, "_")?; }
+            }
+        }
+        writeln!(f)?;
+
+        for j in 0..(N_SLASH - 1) {
+            for _ in 0..j { write!(f, " ")?; }
+            for k in 0..=order {
+                let q0 = -(order as i32) + 2 * (k as i32);
+                let q1 = -(order as i32) + 2 * (k as i32) + 1;
+                let r0 = (order as i32) - (i as i32) - (k as i32);
+                let r1 = (order as i32) - (i as i32) - (k as i32) - 1;
+                let s: i32 = -(k as i32) + (i as i32);
+                let coord0 = fisherman.get_coord() + unsafe { HexDir::new_unchecked(q0, r0, s) };
+                let coord1 = fisherman.get_coord() + unsafe { HexDir::new_unchecked(q1, r1, s) };
+
+                write!(f, "\\")?;
+                // 2nd line: shark
+                board.sharks(f, coord0)?;
+                for _ in 0..(N_US + 2 * (N_SLASH - j - 1) - 8) { write!(f, " ")?; }
+                
+                write!(f, "/")?;
+                if k != order {
+
+                    // 0th line: marlins
+                    board.marlins(f, coord1)?;
+                    for _ in 0..(N_US - 6 + 2 * j) { write!(f, " ")?; }
+                }
+            }
+            writeln!(f)?;
+        }
+        for _ in 0..(N_SLASH - 1) { write!(f, " ")?; }
+
+        for k in 0..=order {
+
+            let q = -(order as i32) + 2 * (k as i32) + 1;
+            let r = (order as i32) - (i as i32) - (k as i32) - 1;
+            let s: i32 = - (k as i32) + (i as i32);
+            let coord = fisherman.get_coord() + unsafe { HexDir::new_unchecked(q, r, s) };
+            write!(f, "\\")?;
+            for _ in 0..N_US { write!(f, "_")?; }
+            write!(f, "/")?;
+            if k != order {
+                board.center(f, coord)?;
+            }
+        }
+        writeln!(f)?;
+    }
+    // ending
+    for i in 0..order {
+        for j in 0..(N_SLASH - 1) {
+            for _ in 0..((i + 1) * (N_US + N_SLASH) + j) { write!(f, " ")?; }
+            for k in 0..(order - i) {
+
+                let q0 = -(order as i32) + 2 * (k as i32 + 1) + (i as i32) - 1;
+                let q1 = -(order as i32) + 2 * (k as i32 + 1) + (i as i32);
+                let r0 = -(i as i32) - (k as i32 + 1);
+                let r1 = -(i as i32) - (k as i32 + 1) - 1;
+                let s: i32 = (order as i32) - (k as i32 + 1) + 1;
+                let coord0 = fisherman.get_coord() + unsafe { HexDir::new_unchecked(q0, r0, s) };
+                let coord1 = fisherman.get_coord() + unsafe { HexDir::new_unchecked(q1, r1, s) };
+                write!(f, "\\")?;
+
+                // 2nd line: shark
+                board.sharks(f, coord0)?;
+                for _ in 0..(N_US + 2 * (N_SLASH - j - 1) - 8) { write!(f, " ")?; }
+                write!(f, "/")?;
+                
+                if k != order - i - 1 {
+                    // 0th line: marlins
+                    board.marlins(f, coord1)?;
+                    for _ in 0..(N_US - 6 + 2 * j) { write!(f, " ")?; }
+                }
+            }
+            writeln!(f)?;
+        }
+
+        for _ in 0..((i + 1) * (N_US + N_SLASH) + N_SLASH - 1) { write!(f, " ")?; }
+        for k in 0..(order - i) {
+
+            let q = -(order as i32) + 2 * (k as i32 + 1) + (i as i32);
+            let r = -(i as i32) - (k as i32 + 1) - 1;
+            let s: i32 = (order as i32) - (k as i32 + 1) + 1;
+            let coord = fisherman.get_coord() + unsafe { HexDir::new_unchecked(q, r, s) };
+            write!(f, "\\")?;
+            for _ in 0..N_US { write!(f, "_")?; }
+            write!(f, "/")?;
+
+            if k != order - i - 1 {
+                board.center(f, coord)?;
+            }
+        }
+        writeln!(f)?;
+    }
+    writeln!(f)
+}
+
+// what a hex is colored by, the first that applies
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Zone {
+    Harbor,
+    // a shark in sight would spot the boat there
+    Danger,
+    // a discovered marlin to capture
+    Reach,
+    // where the boat can sail to
+    Move,
+    Open,
+}
+
+impl Zone {
+    fn background(self) -> Color {
+        match self {
+            Zone::Harbor => Color::DarkBlue,
+            Zone::Danger => Color::DarkRed,
+            Zone::Reach => Color::DarkGreen,
+            Zone::Move => Color::DarkGray,
+            Zone::Open => Color::Default,
+        }
+    }
+
+    // drawn in an empty shark line instead of the color when there are no colors
+    fn mark(self) -> Option<&'static str> {
+        match self {
+            Zone::Danger => Some("!!"),
+            Zone::Reach => Some("++"),
+            Zone::Move => Some("<>"),
+            Zone::Harbor | Zone::Open => None,
+        }
+    }
+}
+
+// draws the three lines inside a hex, each on the color of its zone
+struct Board<'a> {
+    game: &'a Game,
+    fisherman: &'a Fisherman,
+    target: Option<HexCoord>,
+    sharks: Vec<HexCoord>,
+}
+
+impl<'a> Board<'a> {
+    fn new(game: &'a Game, fisherman: &'a Fisherman, target: Option<HexCoord>) -> Self {
+        let sharks = fisherman.get_coord().within_radius(Fisherman::VISUAL_RADIUS).into_iter().filter(|c| game.get_shark_num_at(c) > 0).collect();
+        Self { game, fisherman, target, sharks }
+    }
+
+    fn zone(&self, coord: HexCoord) -> Zone {
+        let boat = self.fisherman.get_coord();
+        let shark_sight = Shark::visual_radius(self.game.get_time_of_day());
+        if self.game.is_harbor(&coord) {
+            Zone::Harbor
+        } else if self.sharks.iter().any(|s| s.distance(&coord) <= shark_sight) {
+            Zone::Danger
+        } else if boat.distance(&coord) <= Fisherman::CAPTURE_RADIUS && self.game.get_discovered_marlin_num_at(&coord) > 0 {
+            Zone::Reach
+        } else if coord != boat && boat.distance(&coord) <= Fisherman::MOVE_RADIUS && self.game.boat_can_enter(&coord) {
+            Zone::Move
+        } else {
+            Zone::Open
+        }
+    }
+
+    // 6 columns wide: the discovered marlins, colored by the weakest one and starred if any is hurt
+    fn marlins(&self, f: &mut Frame, coord: HexCoord) -> fmt::Result {
+        let bg = self.zone(coord).background();
+        let count = self.game.get_discovered_marlin_num_at(&coord);
+        if count == 0 {
+            return f.styled(Style::bg(bg), "      ");
+        }
+        let marlins = (0..count).filter_map(|n| self.game.get_nth_discovered_marlin_at(&coord, n)).collect::<Vec<_>>();
+        let weakest = marlins.iter().map(|m| m.get_hp() as f32 / m.get_initial_hp() as f32).fold(1.0, f32::min);
+        let hurt = if weakest < 1.0 { '*' } else { ' ' };
+        f.styled(Style::fg(hp_color(weakest)).on(bg).bold(), format_args!(" M{:>3}{}", count, hurt))
+    }
+
+    // 8 columns wide, the selected attack target is marked
+    fn sharks(&self, f: &mut Frame, coord: HexCoord) -> fmt::Result {
+        let zone = self.zone(coord);
+        let count = self.game.get_shark_num_at(&coord);
+        if Some(coord) == self.target {
+            f.styled(Style::fg(Color::White).on(Color::Red).bold(), format_args!(">>S{:>3}<<", count))
+        } else if count > 0 {
+            f.styled(Style::fg(Color::White).on(zone.background()).bold(), format_args!("  S{:>3}  ", count))
+        } else if let Some(mark) = zone.mark().filter(|_| f.is_monochrome()) {
+            write!(f, "   {}   ", mark)
+        } else {
+            f.styled(Style::bg(zone.background()), "        ")
+        }
+    }
+
+    // 8 columns wide: the boat, the harbor, or the terrain
+    fn center(&self, f: &mut Frame, coord: HexCoord) -> fmt::Result {
+        let bg = self.zone(coord).background();
+        let boat = coord == self.fisherman.get_coord();
+        if boat && self.game.is_harbor(&coord) {
+            return f.styled(Style::fg(Color::Yellow).on(bg).bold(), "[Player]");
+        } else if self.game.is_harbor(&coord) {
+            return f.styled(Style::bg(bg), "[||||||]");
+        } else if boat {
+            return f.styled(Style::fg(Color::Yellow).on(bg).bold(), " Player ");
+        }
+        let (color, text) = match self.game.get_terrain_at(&coord) {
+            // too dark to see what swims there
+            TerrainType::Water if !self.game.is_visible(&coord) => (Color::Gray, "  ~~~~  ".to_string()),
+            TerrainType::Water => (Color::Default, "        ".to_string()),
+            TerrainType::Reef => (Color::Gray, " ^^^^^^ ".to_string()),
+            TerrainType::Shallows => (Color::Cyan, " ...... ".to_string()),
+            TerrainType::Kelp => (Color::Green, " ;;;;;; ".to_string()),
+            TerrainType::Current => (Color::Cyan, format!("  {}  ", arrow(self.game.get_current_at(&coord)).repeat(4))),
+        };
+        f.styled(Style::fg(color).on(bg), text)
+    }
+}
+
+// green while unhurt, red once at half health or less
+fn hp_color(ratio: f32) -> Color {
+    if ratio >= 1.0 {
+        Color::Green
+    } else if ratio > 0.5 {
+        Color::Yellow
+    } else {
+        Color::Red
+    }
+}
+
+// what the colors, or the marks drawn instead of them, mean, wrapped to fit a standard terminal
+fn draw_legend(f: &mut Frame) -> fmt::Result {
+    const WIDTH: usize = 80;
+    let zones = [(Zone::Harbor, "harbor"), (Zone::Danger, "a shark can see you"), (Zone::Reach, "marlin in reach"), (Zone::Move, "can sail to")];
+    let (items, separator) = if f.is_monochrome() {
+        let mut items = zones.iter().map(|(zone, name)| (Style::PLAIN, format!("{} {}", zone.mark().unwrap_or("[||||||]"), name))).collect::<Vec<_>>();
+        for item in ["M* hurt marlin", "~~~~ too dark to see", "^^ reef", ".. shallows", ";; kelp"] {
+            items.push((Style::PLAIN, item.to_string()));
+        }
+        (items, ", ")
+    } else {
+        let mut items = zones.iter().map(|(zone, name)| (Style::bg(zone.background()), format!(" {} ", name))).collect::<Vec<_>>();
+        items.push((Style::PLAIN, " marlins:".to_string()));
+        for (ratio, name) in [(1.0, "unhurt"), (0.75, "hurt"), (0.5, "weak")] {
+            items.push((Style::fg(hp_color(ratio)).bold(), format!("M {}", name)));
+        }
+        (items, " ")
+    };
+    let mut column = 0;
+    for (style, text) in items {
+        let width = text.chars().count();
+        if column > 0 && column + separator.len() + width > WIDTH {
+            writeln!(f)?;
+            column = 0;
+        } else if column > 0 {
+            write!(f, "{}", separator)?;
+            column += separator.len();
+        }
+        f.styled(style, &text)?;
+        column += width;
+    }
+    writeln!(f)
+}
+
+fn arrow(dir: Option<HexDir>) -> &'static str {
+    match dir.and_then(|d| d.index()) {
+        Some(0) => "↑",
+        Some(1) => "↗",
+        Some(2) => "↘",
+        Some(3) => "↓",
+        Some(4) => "↙",
+        _ => "↖",
+    }
+}
+
+fn render_weather(game: &Game) -> String {
+    match game.get_wind() {
+        Some(wind) => format!("{} {}", game.get_weather(), arrow(Some(wind))),
+        None => game.get_weather().to_string(),
+    }
+}
+
+// time of day and the deadline, in red once it takes every turn left to get back
+fn draw_clock(f: &mut Frame, game: &Game, fisherman: &Fisherman) -> fmt::Result {
+    write!(f, "turn {}, {}", game.get_turn() + 1, game.get_time_of_day())?;
+    if let Some(turns) = game.get_turns_until_dusk_or_dawn() {
+        let next = match game.get_time_of_day() {
+            TimeOfDay::Day => "dusk",
+            TimeOfDay::Night => "dawn",
+        };
+        write!(f, " ({} to {})", turns, next)?;
+    }
+    if let Some(turns_left) = game.get_turns_left() {
+        let distance = fisherman.get_coord().distance(&game.get_nearest_harbor());
+        let style = if turns_left as i32 <= distance { Style::fg(Color::Red) } else { Style::PLAIN };
+        write!(f, ", ")?;
+        f.styled(style, format_args!("{} turns left", turns_left))?;
+    }
+    Ok(())
+}
+
+// points to the nearest harbor
+fn render_compass(game: &Game, fisherman: &Fisherman) -> String {
+    let harbor = game.get_nearest_harbor();
+    let dist = fisherman.get_coord().distance(&harbor);
+    if dist == 0 {
+        return "0NM from harbor".to_string()
+    }
+    let dir = harbor - fisherman.get_coord();
+    let zero = dir.r;
+    let sixty = dir.q;
+    let x = (zero as f32) + (sixty as f32) / 2f32;
+    let y = (sixty as f32) * 3f32.sqrt() / 2f32;
+    let rad = f32::atan2(y, x); // (-PI, PI)
+    let rad = if rad < 0.0 { f32::consts::TAU + rad } else { rad };
+    let deg = rad / f32::consts::PI * 180f32;
+    let (arrow, dir) = match deg {
+        0.0..22.5 | 337.5..=360.0 => ("↑", "N"),
+        22.5..67.5   => ("↗", "NE"),
+        67.5..112.5  => ("→", "E"),
+        112.5..157.5 => ("↘", "SE"),
+        157.5..202.5 => ("↓", "S"),
+        202.5..247.5 => ("↙", "SW"),
+        247.5..292.5 => ("←", "W"),
+        292.5..337.5 => ("↖", "NW"),
+        _ => panic!("degree conversion failed")
+    };
+    format!("{} {}°{:2>} {}NM from harbor", arrow, deg as i32, dir, dist)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{campaign::{Campaign, TerrainConfig}, level::UserAction};
+
+    // a game paused with the boat just north of the harbor, a discovered marlin north-east of it and a shark three cells north
+    fn game_with_creatures() -> Game {
+        let mut campaign = Campaign::default();
+        campaign.levels[0].terrain = TerrainConfig { cells: vec![], generator: None };
+        campaign.levels[0].day_cycle = None;
+        let mut save: serde_json::Value = serde_json::from_str(&Game::with_campaign(&campaign, 14).save()).unwrap();
+        let boat = HexCoord::ZERO + HexDir::NORTH;
+        save["current_level"]["fisherman"]["coordinate"] = serde_json::to_value(boat).unwrap();
+        save["current_level"]["map"] = serde_json::json!([
+            [boat + HexDir::NORTHEAST, { "marlins": [{ "discovered": true, "hp": 4 }], "sharks": [] }],
+            [HexCoord::new(0, 4, -4), { "marlins": [], "sharks": [{ "hp": 2 }] }],
+        ]);
+        Game::load(&save.to_string()).unwrap()
+    }
+
+    #[test]
+    fn hexes_are_zoned_by_what_happens_there() {
+        let game = game_with_creatures();
+        let fisherman = game.get_fisherman();
+        let boat = fisherman.get_coord();
+        let board = Board::new(&game, &fisherman, None);
+        assert_eq!(board.zone(HexCoord::ZERO), Zone::Harbor);
+        // the shark sees two cells around it, even where the boat could sail
+        assert_eq!(board.zone(boat + HexDir::NORTH), Zone::Danger);
+        assert_eq!(board.zone(HexCoord::new(0, 4, -4)), Zone::Danger);
+        assert_eq!(board.zone(boat + HexDir::NORTHEAST), Zone::Reach);
+        assert_eq!(board.zone(boat + HexDir::NORTHWEST), Zone::Move);
+        assert_eq!(board.zone(boat + HexDir::SOUTHEAST), Zone::Move);
+        assert_eq!(board.zone(boat), Zone::Open);
+        assert_eq!(board.zone(HexCoord::new(-2, 2, 0) + HexDir::SOUTHWEST), Zone::Open);
+        // each zone has its mark without colors
+        let text = render_game(&game);
+        for mark in ["   !!   ", "   ++   ", "   <>   "] {
+            assert!(text.contains(mark), "no {:?} in\n{}", mark, text);
+        }
+    }
+
+    #[test]
+    fn legend_fits_a_standard_terminal() {
+        for mut frame in [Frame::new(), Frame::monochrome()] {
+            draw_legend(&mut frame).unwrap();
+            assert!(frame.lines().len() > 1);
+            assert!(frame.lines().iter().all(|line| line.len() <= 80));
+        }
+    }
+
+    #[test]
+    fn start_board() {
+        let game = Game::with_campaign(&Campaign::default(), 14);
+        assert_eq!(render_game(&game), r#"                                  ______
+                                 /      \
+                          ______/        \______
+                         /      \        /      \
+                  ______/        \______/        \______
+                 /      \        /      \        /      \
+          ______/        \______/        \______/        \______
+         /      \        /      \        /      \        /      \
+  ______/        \______/        \______/        \______/        \______
+ /      \        /      \        /      \        /      \        /      \
+/        \______/        \______/        \______/        \______/        \
+\        /      \        /      \        /      \        /      \        /
+ \______/        \______/        \______/        \______/        \______/
+ /      \        /      \        /      \        /      \        /      \
+/        \______/        \______/        \______/        \______/        \
+\        /      \        /      \   <>   /      \        /      \        /
+ \______/        \______/        \______/        \______/        \______/
+ /      \        /      \   <>   /      \   <>   /      \        /      \
+/        \______/        \______/[Player]\______/        \______/        \
+\        /      \        /      \        /      \        /      \        /
+ \______/        \______/        \______/        \______/        \______/
+ /      \        /      \   <>   /      \   <>   /      \        /      \
+/        \______/        \______/        \______/        \______/        \
+\        /      \        /      \   <>   /      \        /      \        /
+ \______/        \______/        \______/        \______/        \______/
+ /      \        /      \        /      \        /      \        /      \
+/        \______/        \______/        \______/        \______/        \
+\        /      \        /      \        /      \        /      \        /
+ \______/        \______/        \______/        \______/        \______/
+        \        /      \        /      \        /      \        /
+         \______/        \______/        \______/        \______/
+                \        /      \        /      \        /
+                 \______/        \______/        \______/
+                        \        /      \        /
+                         \______/        \______/
+                                \        /
+                                 \______/
+
+[||||||] harbor, !! a shark can see you, ++ marlin in reach, <> can sail to
+M* hurt marlin, ~~~~ too dark to see, ^^ reef, .. shallows, ;; kelp
+target: 0/5, 0NM from harbor, HP: ♥ ♥ ♥ ♥ ♥ , turn 1, day, calm, 0 coins
+"#);
+    }
+
+    #[test]
+    fn board_after_a_few_turns() {
+        let mut game = Game::with_campaign(&Campaign::default(), 14);
+        for action in [UserAction::Move(HexDir::NORTH), UserAction::Move(HexDir::NORTH), UserAction::Move(HexDir::NORTHEAST), UserAction::Discover] {
+            game.handle_action(action.into());
+        }
+        assert_eq!(render_game(&game), r#"                                  ______
+                                 /      \
+                          ______/        \______
+                         /      \        /      \
+                  ______/        \______/        \______
+                 /      \        / M  1 \        /      \
+          ______/        \______/        \______/        \______
+         /      \        /      \        /      \        /      \
+  ______/        \______/        \______/        \______/        \______
+ /      \        /      \        /      \        /      \        /      \
+/        \______/        \______/        \______/        \______/        \
+\        /      \        /      \        /      \        /      \        /
+ \______/        \______/        \______/        \______/        \______/
+ /      \        /      \        / M  1 \        /      \        /      \
+/        \______/        \______/        \______/        \______/        \
+\        /      \        /      \   ++   /      \        /      \        /
+ \______/        \______/        \______/        \______/        \______/
+ /      \        /      \   <>   /      \   <>   /      \        /      \
+/        \______/        \______/ Player \______/        \______/        \
+\        /      \        /      \        /      \        /      \        /
+ \______/        \______/        \______/        \______/        \______/
+ /      \        /      \   <>   /      \   <>   /      \        /      \
+/        \______/        \______/        \______/        \______/        \
+\        /      \        /      \   <>   /      \        /      \        /
+ \______/        \______/        \______/        \______/        \______/
+ /      \        /      \        /      \        /      \        /      \
+/        \______/        \______/        \______/        \______/        \
+\        /      \        /      \        /      \        /      \        /
+ \______/        \______/[||||||]\______/        \______/        \______/
+        \        /      \        /      \        /      \        /
+         \______/        \______/        \______/        \______/
+                \        /      \        /      \        /
+                 \______/        \______/        \______/
+                        \        /      \        /
+                         \______/        \______/
+                                \        /
+                                 \______/
+
+[||||||] harbor, !! a shark can see you, ++ marlin in reach, <> can sail to
+M* hurt marlin, ~~~~ too dark to see, ^^ reef, .. shallows, ;; kelp
+target: 0/5, ↓ 199°S 3NM from harbor, HP: ♥ ♥ ♥ ♥ ♥ , turn 5, day, calm, 0 coins
+"#);
+    }
+}
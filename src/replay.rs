@@ -0,0 +1,167 @@
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use crate::{campaign::Campaign, level::{Game, InputResult, LevelChange, LevelChangeError, UserAction, WasmUserAction}};
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct ReplayTurn {
+    pub action: UserAction,
+    pub result: InputResult,
+}
+
+/// Seed, campaign and every action of a game. Since the game is deterministic, this is enough to play it again.
+/// Bump `Replay::VERSION` whenever the format changes, e.g. a new action.
+#[wasm_bindgen]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Replay {
+    version: u32,
+    seed: u64,
+    campaign: Campaign,
+    turns: Vec<ReplayTurn>,
+    // retries and level jumps, each with the number of turns played before it
+    #[serde(default)]
+    level_changes: Vec<(usize, LevelChange)>,
+}
+
+impl Replay {
+    // 2: level changes
+    // 3: buying upgrades
+    const VERSION: u32 = 3;
+
+    pub(crate) fn new(seed: u64, campaign: Campaign, turns: Vec<ReplayTurn>, level_changes: Vec<(usize, LevelChange)>) -> Self {
+        Self {
+            version: Self::VERSION,
+            seed,
+            campaign,
+            turns,
+            level_changes,
+        }
+    }
+
+    pub fn turns(&self) -> &[ReplayTurn] {
+        &self.turns
+    }
+
+    /// Feeds every recorded action to a fresh game and checks it reacts the same way.
+    pub fn verify(&self) -> Result<(), ReplayError> {
+        let mut game = self.new_game();
+        for (turn, recorded) in self.turns.iter().enumerate() {
+            self.apply_level_changes(&mut game, turn).map_err(|error| ReplayError::RejectedLevelChange { turn, error })?;
+            let actual = game.handle_action(recorded.action.into());
+            if actual != recorded.result {
+                return Err(ReplayError::Diverged { turn, expected: recorded.result, actual });
+            }
+        }
+        Ok(())
+    }
+
+    fn apply_level_changes(&self, game: &mut Game, turn: usize) -> Result<(), LevelChangeError> {
+        for (_, change) in self.level_changes.iter().filter(|(t, _)| *t == turn) {
+            game.change_level(*change)?;
+        }
+        Ok(())
+    }
+}
+
+#[wasm_bindgen]
+impl Replay {
+    pub fn from_json(json: &str) -> Result<Replay, String> {
+        let replay: Replay = serde_json::from_str(json).map_err(|e| format!("malformed replay: {}", e))?;
+        if replay.version != Self::VERSION {
+            return Err(format!("unsupported replay version {}, expected {}", replay.version, Self::VERSION));
+        }
+        replay.campaign.validate().map_err(|e| e.to_string())?;
+        Ok(replay)
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+
+    /// The game in the state before the first recorded action.
+    pub fn new_game(&self) -> Game {
+        Game::with_campaign(&self.campaign, self.seed)
+    }
+
+    /// Applies the level changes recorded right before `turn`, call it before playing each turn
+    /// and once more with `get_turn_count()` at the end.
+    pub fn prepare_turn(&self, game: &mut Game, turn: usize) -> Result<(), String> {
+        self.apply_level_changes(game, turn).map_err(|e| format!("replay diverged at turn {}: {}", turn, e))
+    }
+
+    #[inline]
+    pub fn get_seed(&self) -> u64 {
+        self.seed
+    }
+
+    #[inline]
+    pub fn get_turn_count(&self) -> usize {
+        self.turns.len()
+    }
+
+    pub fn get_action_at(&self, turn: usize) -> Option<WasmUserAction> {
+        Some(self.turns.get(turn)?.action.into())
+    }
+
+    pub fn get_result_at(&self, turn: usize) -> Option<InputResult> {
+        Some(self.turns.get(turn)?.result)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReplayError {
+    /// the game answered differently than when the replay was recorded
+    Diverged { turn: usize, expected: InputResult, actual: InputResult },
+    /// a recorded retry or level jump was refused
+    RejectedLevelChange { turn: usize, error: LevelChangeError },
+}
+
+impl Display for ReplayError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReplayError::Diverged { turn, expected, actual } => write!(f, "replay diverged at turn {}: recorded {:?}, got {:?}", turn, expected, actual),
+            ReplayError::RejectedLevelChange { turn, error } => write!(f, "replay diverged at turn {}: {}", turn, error),
+        }
+    }
+}
+
+impl std::error::Error for ReplayError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map::HexDir;
+
+    // a few turns on the default campaign, with a retry and a jump back to the first level in between
+    fn play() -> Game {
+        let mut game = Game::with_campaign(&Campaign::default(), 7);
+        for dir in [HexDir::NORTH, HexDir::NORTHEAST] {
+            game.handle_action(UserAction::Move(dir).into());
+        }
+        game.change_level(LevelChange::Retry).unwrap();
+        game.handle_action(UserAction::Discover.into());
+        game.change_level(LevelChange::JumpTo(0)).unwrap();
+        for dir in [HexDir::SOUTH, HexDir::SOUTHWEST, HexDir::NORTH] {
+            game.handle_action(UserAction::Move(dir).into());
+        }
+        game
+    }
+
+    #[test]
+    fn recorded_game_verifies() {
+        let json = play().get_replay().to_json();
+        let replay = Replay::from_json(&json).unwrap();
+        assert_eq!(replay.get_turn_count(), 6);
+        assert_eq!(replay.level_changes.len(), 2);
+        assert_eq!(replay.verify(), Ok(()));
+    }
+
+    #[test]
+    fn tampered_result_diverges() {
+        let mut replay = play().get_replay();
+        replay.turns[3].result = InputResult::LevelPassed;
+        assert_eq!(replay.verify(), Err(ReplayError::Diverged { turn: 3, expected: InputResult::LevelPassed, actual: InputResult::Ok }));
+    }
+}
@@ -0,0 +1,141 @@
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use crate::level::Level;
+
+/// Points earned by passing one level, and what they were earned for.
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct LevelScore {
+    pub level_index: usize,
+    /// marlins up to the target
+    pub marlins: usize,
+    /// marlins beyond the target
+    pub surplus: usize,
+    pub hp_left: usize,
+    pub turns: usize,
+    pub sharks_killed: usize,
+    /// marlins captured without being hurt by a net or a shark first
+    pub undamaged_captures: usize,
+}
+
+impl LevelScore {
+    const MARLIN_POINTS: usize = 10;
+    const SURPLUS_POINTS: usize = 15;
+    const HP_POINTS: usize = 20;
+    // turns under par are worth this much each, par depends on the target
+    const TURN_POINTS: usize = 2;
+    const PAR_TURNS_PER_MARLIN: usize = 10;
+    const SHARK_POINTS: usize = 25;
+    const UNDAMAGED_CAPTURE_POINTS: usize = 5;
+
+    /// Scores a level the fisherman just brought home.
+    pub fn of(level_index: usize, level: &Level) -> Self {
+        let fisherman = level.get_fisherman();
+        let captured = fisherman.get_captured_marlins();
+        Self {
+            level_index,
+            marlins: captured.min(level.get_target()),
+            surplus: captured.saturating_sub(level.get_target()),
+            hp_left: fisherman.get_hp().max(0) as usize,
+            turns: level.get_turn(),
+            sharks_killed: level.get_sharks_killed(),
+            undamaged_captures: fisherman.get_undamaged_captures(),
+        }
+    }
+
+    fn par_turns(&self) -> usize {
+        // the target is reached with no surplus at worst
+        (self.marlins * Self::PAR_TURNS_PER_MARLIN).max(1)
+    }
+}
+
+#[wasm_bindgen]
+impl LevelScore {
+    pub fn marlin_points(&self) -> usize {
+        self.marlins * Self::MARLIN_POINTS
+    }
+
+    pub fn surplus_points(&self) -> usize {
+        self.surplus * Self::SURPLUS_POINTS
+    }
+
+    pub fn hp_points(&self) -> usize {
+        self.hp_left * Self::HP_POINTS
+    }
+
+    pub fn turn_points(&self) -> usize {
+        self.par_turns().saturating_sub(self.turns) * Self::TURN_POINTS
+    }
+
+    pub fn shark_points(&self) -> usize {
+        self.sharks_killed * Self::SHARK_POINTS
+    }
+
+    pub fn undamaged_capture_points(&self) -> usize {
+        self.undamaged_captures * Self::UNDAMAGED_CAPTURE_POINTS
+    }
+
+    pub fn total(&self) -> usize {
+        self.marlin_points() + self.surplus_points() + self.hp_points() + self.turn_points() + self.shark_points() + self.undamaged_capture_points()
+    }
+}
+
+impl Display for LevelScore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Level {}", self.level_index + 1)?;
+        writeln!(f, "  marlins           {:>4} x {:>2} = {:>5}", self.marlins, Self::MARLIN_POINTS, self.marlin_points())?;
+        writeln!(f, "  surplus marlins   {:>4} x {:>2} = {:>5}", self.surplus, Self::SURPLUS_POINTS, self.surplus_points())?;
+        writeln!(f, "  HP left           {:>4} x {:>2} = {:>5}", self.hp_left, Self::HP_POINTS, self.hp_points())?;
+        writeln!(f, "  turns under par   {:>4} x {:>2} = {:>5}", self.par_turns().saturating_sub(self.turns), Self::TURN_POINTS, self.turn_points())?;
+        writeln!(f, "  sharks killed     {:>4} x {:>2} = {:>5}", self.sharks_killed, Self::SHARK_POINTS, self.shark_points())?;
+        writeln!(f, "  undamaged catches {:>4} x {:>2} = {:>5}", self.undamaged_captures, Self::UNDAMAGED_CAPTURE_POINTS, self.undamaged_capture_points())?;
+        write!(f, "  total                        {:>5}", self.total())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::campaign::Campaign;
+
+    // a level brought home with these numbers, set through its save since they can't be reached directly
+    fn finished_level(target: usize, captured: usize, undamaged: usize, hp: i32, turn: usize, sharks_killed: usize) -> Level {
+        let mut config = Campaign::default().levels[0].clone();
+        config.target = target;
+        let mut level: serde_json::Value = serde_json::to_value(Level::new(&config, 1)).unwrap();
+        level["fisherman"]["captured_marlins"] = captured.into();
+        level["fisherman"]["undamaged_captures"] = undamaged.into();
+        level["fisherman"]["hp"] = hp.into();
+        level["turn"] = turn.into();
+        level["sharks_killed"] = sharks_killed.into();
+        serde_json::from_value(level).unwrap()
+    }
+
+    #[test]
+    fn every_term_is_counted() {
+        let score = LevelScore::of(2, &finished_level(3, 5, 2, 3, 12, 1));
+        assert_eq!(score, LevelScore { level_index: 2, marlins: 3, surplus: 2, hp_left: 3, turns: 12, sharks_killed: 1, undamaged_captures: 2 });
+        assert_eq!(score.marlin_points(), 30);
+        assert_eq!(score.surplus_points(), 30);
+        assert_eq!(score.hp_points(), 60);
+        // par is 10 turns per marlin of the target
+        assert_eq!(score.turn_points(), (30 - 12) * 2);
+        assert_eq!(score.shark_points(), 25);
+        assert_eq!(score.undamaged_capture_points(), 10);
+        assert_eq!(score.total(), 30 + 30 + 60 + 36 + 25 + 10);
+    }
+
+    #[test]
+    fn no_points_below_zero() {
+        let score = LevelScore::of(0, &finished_level(2, 2, 0, -3, 45, 0));
+        assert_eq!(score.hp_left, 0);
+        assert_eq!(score.surplus, 0);
+        assert_eq!(score.turn_points(), 0);
+        assert_eq!(score.total(), 20);
+        // a level with nothing to catch still has a par of one turn
+        assert_eq!(LevelScore { turns: 0, ..LevelScore::default() }.turn_points(), 2);
+    }
+}
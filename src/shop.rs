@@ -0,0 +1,125 @@
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+/// What the harbor shop sells. Each upgrade can be bought a few times, every level costing more than the last.
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Upgrade {
+    /// better nets catch more often
+    Net,
+    /// a sharper harpoon hurts sharks more
+    Harpoon,
+    /// hull plating takes more bites
+    Hull,
+    /// longer-range binoculars find marlins farther away
+    Binoculars,
+}
+
+impl Upgrade {
+    pub const ALL: [Upgrade; 4] = [Upgrade::Net, Upgrade::Harpoon, Upgrade::Hull, Upgrade::Binoculars];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|u| u.name() == name)
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Upgrade::Net => "net",
+            Upgrade::Harpoon => "harpoon",
+            Upgrade::Hull => "hull",
+            Upgrade::Binoculars => "binoculars",
+        }
+    }
+
+    /// What one level of the upgrade does.
+    pub fn effect(&self) -> &'static str {
+        match self {
+            Upgrade::Net => "+10% capture success rate",
+            Upgrade::Harpoon => "+1 attack power",
+            Upgrade::Hull => "+1 HP",
+            Upgrade::Binoculars => "+1NM discover range",
+        }
+    }
+
+    pub fn max_level(&self) -> u32 {
+        match self {
+            Upgrade::Net => 3,
+            Upgrade::Harpoon => 2,
+            Upgrade::Hull => 3,
+            Upgrade::Binoculars => 2,
+        }
+    }
+
+    /// Price of the next level, bought at `level`.
+    pub fn price(&self, level: u32) -> usize {
+        let base = match self {
+            Upgrade::Net => 20,
+            Upgrade::Harpoon => 30,
+            Upgrade::Hull => 25,
+            Upgrade::Binoculars => 25,
+        };
+        base * (level as usize + 1)
+    }
+}
+
+impl Display for Upgrade {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// Levels of every upgrade bought so far.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Equipment {
+    pub net: u32,
+    pub harpoon: u32,
+    pub hull: u32,
+    pub binoculars: u32,
+}
+
+impl Equipment {
+    const NET_BONUS: f32 = 0.1;
+
+    pub fn level(&self, upgrade: Upgrade) -> u32 {
+        match upgrade {
+            Upgrade::Net => self.net,
+            Upgrade::Harpoon => self.harpoon,
+            Upgrade::Hull => self.hull,
+            Upgrade::Binoculars => self.binoculars,
+        }
+    }
+
+    pub(crate) fn upgrade(&mut self, upgrade: Upgrade) {
+        match upgrade {
+            Upgrade::Net => self.net += 1,
+            Upgrade::Harpoon => self.harpoon += 1,
+            Upgrade::Hull => self.hull += 1,
+            Upgrade::Binoculars => self.binoculars += 1,
+        }
+    }
+
+    /// Price of the next level of `upgrade`, none once it is fully upgraded.
+    pub fn next_price(&self, upgrade: Upgrade) -> Option<usize> {
+        let level = self.level(upgrade);
+        (level < upgrade.max_level()).then(|| upgrade.price(level))
+    }
+
+    pub fn capture_bonus(&self) -> f32 {
+        self.net as f32 * Self::NET_BONUS
+    }
+
+    pub fn attack_bonus(&self) -> i32 {
+        self.harpoon as i32
+    }
+
+    pub fn hp_bonus(&self) -> i32 {
+        self.hull as i32
+    }
+
+    pub fn discover_bonus(&self) -> i32 {
+        self.binoculars as i32
+    }
+}
@@ -0,0 +1,93 @@
+use std::fmt::Display;
+
+use crate::{campaign::LevelConfig, level::Level, policy::Policy};
+
+/// Outcome of one level played by an automated policy.
+#[derive(Debug, Clone, Copy)]
+pub struct LevelStats {
+    pub won: bool,
+    /// the level was still running when the turn limit was hit, or the policy kept choosing refused actions
+    pub timed_out: bool,
+    pub turns: usize,
+    pub hp_lost: i32,
+    pub marlins_captured: usize,
+    pub sharks_killed: usize,
+}
+
+// a policy that keeps choosing refused actions would otherwise never finish
+const MAX_CONSECUTIVE_REFUSALS: usize = 100;
+
+/// Plays one level with `policy` choosing every action, without any user interface.
+pub fn simulate_level(config: &LevelConfig, seed: u64, max_turns: usize, policy: &mut dyn Policy) -> LevelStats {
+    let mut level = Level::new(config, seed);
+    let mut refusals = 0;
+    let mut outcome = None;
+    while level.get_turn() < max_turns && refusals < MAX_CONSECUTIVE_REFUSALS {
+        let action = policy.choose_action(&level.view());
+        match level.advance(action) {
+            Ok(None) => refusals = 0,
+            Ok(Some(res)) => {
+                outcome = Some(res.is_ok());
+                break;
+            },
+            Err(_) => refusals += 1,
+        }
+    }
+    let fisherman = level.get_fisherman();
+    LevelStats {
+        won: outcome == Some(true),
+        timed_out: outcome.is_none(),
+        turns: level.get_turn(),
+        hp_lost: fisherman.get_initial_hp() - fisherman.get_hp().max(0),
+        marlins_captured: fisherman.get_captured_marlins(),
+        sharks_killed: level.get_sharks_killed(),
+    }
+}
+
+/// Totals over many simulated levels.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Summary {
+    pub runs: usize,
+    pub wins: usize,
+    pub timeouts: usize,
+    pub turns: usize,
+    pub hp_lost: i64,
+    pub marlins_captured: usize,
+    pub sharks_killed: usize,
+}
+
+impl Summary {
+    pub fn add(&mut self, stats: &LevelStats) {
+        self.runs += 1;
+        self.wins += stats.won as usize;
+        self.timeouts += stats.timed_out as usize;
+        self.turns += stats.turns;
+        self.hp_lost += stats.hp_lost as i64;
+        self.marlins_captured += stats.marlins_captured;
+        self.sharks_killed += stats.sharks_killed;
+    }
+
+    pub fn win_rate(&self) -> f64 {
+        self.wins as f64 / self.runs.max(1) as f64
+    }
+
+    fn average(&self, total: f64) -> f64 {
+        total / self.runs.max(1) as f64
+    }
+}
+
+impl Display for Summary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "runs {:>6} | win {:>5.1}% | timeout {:>5.1}% | turns {:>7.1} | HP lost {:>5.2} | marlins {:>6.2} | shark kills {:>5.2}",
+            self.runs,
+            self.win_rate() * 100.0,
+            self.average(self.timeouts as f64) * 100.0,
+            self.average(self.turns as f64),
+            self.average(self.hp_lost as f64),
+            self.average(self.marlins_captured as f64),
+            self.average(self.sharks_killed as f64),
+        )
+    }
+}
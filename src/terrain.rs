@@ -0,0 +1,213 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use crate::{campaign::{LevelConfig, TerrainConfig, TerrainGenerator}, map::{hex_map_entries, HexCoord, HexDir}};
+
+/// What a cell is made of. Open water unless a level says otherwise.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Terrain {
+    #[default]
+    Water,
+    /// rocks nothing can swim or sail into
+    Reef,
+    /// too shallow for sharks
+    Shallows,
+    /// hides marlins from the binocular
+    Kelp,
+    /// pushes the boat one cell in this direction at the end of every turn spent on it
+    Current(HexDir),
+}
+
+impl Terrain {
+    pub fn boat_can_enter(&self) -> bool {
+        *self != Terrain::Reef
+    }
+
+    pub fn marlins_can_enter(&self) -> bool {
+        *self != Terrain::Reef
+    }
+
+    pub fn sharks_can_enter(&self) -> bool {
+        !matches!(self, Terrain::Reef | Terrain::Shallows)
+    }
+
+    pub fn hides_marlins(&self) -> bool {
+        *self == Terrain::Kelp
+    }
+
+    pub fn kind(&self) -> TerrainType {
+        match self {
+            Terrain::Water => TerrainType::Water,
+            Terrain::Reef => TerrainType::Reef,
+            Terrain::Shallows => TerrainType::Shallows,
+            Terrain::Kelp => TerrainType::Kelp,
+            Terrain::Current(_) => TerrainType::Current,
+        }
+    }
+}
+
+impl std::fmt::Display for Terrain {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Terrain::Water => write!(f, "open water"),
+            Terrain::Reef => write!(f, "a reef"),
+            Terrain::Shallows => write!(f, "shallows"),
+            Terrain::Kelp => write!(f, "kelp"),
+            Terrain::Current(dir) => write!(f, "a current heading {}", dir),
+        }
+    }
+}
+
+/// `Terrain` without the current direction, for JS.
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TerrainType {
+    Water,
+    Reef,
+    Shallows,
+    Kelp,
+    Current,
+}
+
+/// The terrain of a whole level: its harbors, the fixed cells of the level file, and the generator for everything else.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TerrainMap {
+    #[serde(with = "hex_map_entries")]
+    fixed: HashMap<HexCoord, Terrain>,
+    generator: Option<TerrainGenerator>,
+    // saves from before harbors could move have theirs at the origin
+    #[serde(default = "LevelConfig::default_harbors")]
+    harbors: Vec<HexCoord>,
+    seed: u64,
+}
+
+impl Default for TerrainMap {
+    fn default() -> Self {
+        Self {
+            fixed: HashMap::new(),
+            generator: None,
+            harbors: LevelConfig::default_harbors(),
+            seed: 0,
+        }
+    }
+}
+
+impl TerrainMap {
+    // the generator keeps the harbors and the cells around them open
+    const CLEAR_RADIUS: i32 = 1;
+    // reefs further out may still close a harbor in, the boat has to be able to sail this far from each
+    const ESCAPE_RADIUS: i32 = 6;
+    // new seeds to try for a generated ocean that traps a harbor, the fixed cells may leave no way out
+    const MAX_RESEEDS: u64 = 100;
+
+    pub fn new(config: &TerrainConfig, harbors: &[HexCoord], seed: u64) -> Self {
+        let mut terrain = Self {
+            fixed: config.cells.iter().copied().collect(),
+            generator: config.generator,
+            harbors: harbors.to_vec(),
+            seed,
+        };
+        if terrain.generator.is_some() {
+            // the next seeds in line, so the ocean still only depends on the level seed
+            for reseed in 1..=Self::MAX_RESEEDS {
+                if terrain.harbors.iter().all(|h| terrain.can_escape(h)) {
+                    break;
+                }
+                terrain.seed = seed.wrapping_add(reseed);
+            }
+        }
+        terrain
+    }
+
+    /// Whether the boat can sail from `harbor` to open water, `ESCAPE_RADIUS` cells away.
+    pub fn can_escape(&self, harbor: &HexCoord) -> bool {
+        let passable = |c: &HexCoord| self.at(c).boat_can_enter();
+        let max_cost = 3 * Self::ESCAPE_RADIUS as u32;
+        harbor.ring(Self::ESCAPE_RADIUS).filter(passable).any(|goal| harbor.find_path(&goal, max_cost, passable, |_, _| 1).is_some())
+    }
+
+    pub fn harbors(&self) -> &[HexCoord] {
+        &self.harbors
+    }
+
+    pub fn is_harbor(&self, coord: &HexCoord) -> bool {
+        self.harbors.contains(coord)
+    }
+
+    /// The harbor closest to `coord`, the first listed one on a tie.
+    pub fn nearest_harbor(&self, coord: &HexCoord) -> HexCoord {
+        *self.harbors.iter().min_by_key(|h| h.distance(coord)).unwrap()
+    }
+
+    pub fn harbor_distance(&self, coord: &HexCoord) -> i32 {
+        self.nearest_harbor(coord).distance(coord)
+    }
+
+    pub fn at(&self, coord: &HexCoord) -> Terrain {
+        if let Some(terrain) = self.fixed.get(coord) {
+            return *terrain;
+        }
+        let Some(generator) = &self.generator else {
+            return Terrain::Water;
+        };
+        if self.harbor_distance(coord) <= Self::CLEAR_RADIUS {
+            return Terrain::Water;
+        }
+        // every cell is decided on its own from the seed, so the ocean is the same however it is explored
+        let hash = cell_hash(self.seed, coord);
+        let roll = (hash >> 40) as f32 / (1u64 << 24) as f32;
+        let mut threshold = 0.0;
+        for (fraction, terrain) in [
+            (generator.reef, Terrain::Reef),
+            (generator.shallows, Terrain::Shallows),
+            (generator.kelp, Terrain::Kelp),
+            (generator.current, Terrain::Current(HexDir::from_index(hash as usize % 6))),
+        ] {
+            threshold += fraction;
+            if roll < threshold {
+                return terrain;
+            }
+        }
+        Terrain::Water
+    }
+}
+
+pub(crate) fn cell_hash(seed: u64, coord: &HexCoord) -> u64 {
+    hash2(seed, coord.q, coord.r)
+}
+
+// splitmix64 of the seed and a pair of integers
+pub(crate) fn hash2(seed: u64, a: i32, b: i32) -> u64 {
+    let mut z = seed ^ ((a as u32 as u64) << 32 | b as u32 as u64);
+    z = z.wrapping_add(0x9E3779B97F4A7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::campaign::TerrainGenerator;
+
+    #[test]
+    fn generated_reefs_never_close_a_harbor_in() {
+        // past the point where reefs stop leaving a way through
+        let config = TerrainConfig {
+            cells: vec![],
+            generator: Some(TerrainGenerator { reef: 0.55, shallows: 0.0, kelp: 0.0, current: 0.0 }),
+        };
+        let harbors = [HexCoord::ZERO, HexCoord::new(9, -4, -5)];
+        let mut reseeded = 0;
+        for seed in 0..200 {
+            let terrain = TerrainMap::new(&config, &harbors, seed);
+            assert!(harbors.iter().all(|h| terrain.can_escape(h)), "seed {}", seed);
+            reseeded += (terrain.seed != seed) as usize;
+        }
+        // the test only means something if some oceans had to be drawn again
+        assert!(reseeded > 0);
+    }
+}
@@ -0,0 +1,111 @@
+use std::collections::VecDeque;
+
+use crate::{driver::{Command, UserInterface}, entities::ActionError, highscore::RunRecord, level::{Game, LevelChange}, policy::Policy, render};
+
+/// Plays a fixed list of commands and keeps everything the game showed, for tests.
+/// Quits once the commands run out.
+#[derive(Debug, Default)]
+pub struct ScriptedUI {
+    commands: VecDeque<Command>,
+    level_changes: VecDeque<Option<LevelChange>>,
+    /// the board at every render, as plain text
+    pub boards: Vec<String>,
+    /// prompts and refused actions, in order
+    pub messages: Vec<String>,
+    /// every run that was over, unnamed
+    pub runs: Vec<RunRecord>,
+}
+
+impl ScriptedUI {
+    /// `level_changes` answers the questions asked after a level is over, quitting once they run out.
+    pub fn new(commands: impl IntoIterator<Item = Command>, level_changes: impl IntoIterator<Item = Option<LevelChange>>) -> Self {
+        Self {
+            commands: commands.into_iter().collect(),
+            level_changes: level_changes.into_iter().collect(),
+            ..Self::default()
+        }
+    }
+
+    pub fn last_board(&self) -> Option<&str> {
+        self.boards.last().map(|b| b.as_str())
+    }
+}
+
+impl UserInterface for ScriptedUI {
+    fn start(&mut self) {}
+
+    fn render(&mut self, game: &Game) {
+        self.boards.push(render::render_game(game));
+    }
+
+    fn input(&mut self, _game: &Game) -> Command {
+        self.commands.pop_front().unwrap_or(Command::Quit)
+    }
+
+    fn invalid_input(&mut self, error: &ActionError) {
+        self.messages.push(format!("Invalid input: {}", error));
+    }
+
+    fn prompt(&mut self, msg: String) {
+        self.messages.push(msg);
+    }
+
+    fn pause(&mut self) -> bool {
+        true
+    }
+
+    fn ask_level_change(&mut self, _game: &Game) -> Option<LevelChange> {
+        self.level_changes.pop_front().flatten()
+    }
+
+    fn run_over(&mut self, game: &Game) {
+        self.runs.push(RunRecord::new(game, String::new(), 0));
+    }
+}
+
+/// Lets a policy play without showing anything, e.g. to soak-test a campaign.
+/// Retries failed levels and quits after `max_turns` turns.
+pub struct HeadlessUI {
+    policy: Box<dyn Policy>,
+    max_turns: usize,
+    turns: usize,
+}
+
+impl HeadlessUI {
+    pub fn new(policy: Box<dyn Policy>, max_turns: usize) -> Self {
+        Self { policy, max_turns, turns: 0 }
+    }
+
+    /// How many actions the policy has chosen.
+    pub fn get_turns(&self) -> usize {
+        self.turns
+    }
+}
+
+impl UserInterface for HeadlessUI {
+    fn start(&mut self) {}
+
+    fn render(&mut self, _game: &Game) {}
+
+    fn input(&mut self, game: &Game) -> Command {
+        if self.turns >= self.max_turns {
+            return Command::Quit;
+        }
+        self.turns += 1;
+        Command::Action(self.policy.choose_action(&game.view()))
+    }
+
+    fn invalid_input(&mut self, _error: &ActionError) {}
+
+    fn prompt(&mut self, _msg: String) {}
+
+    fn pause(&mut self) -> bool {
+        true
+    }
+
+    fn ask_level_change(&mut self, _game: &Game) -> Option<LevelChange> {
+        Some(LevelChange::Retry)
+    }
+
+    fn run_over(&mut self, _game: &Game) {}
+}
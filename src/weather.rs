@@ -0,0 +1,180 @@
+use std::fmt::Display;
+
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use crate::{campaign::WeatherConfig, map::HexDir};
+
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum WeatherKind {
+    #[default]
+    Calm,
+    /// marlins drift downwind
+    Wind,
+    /// shortens the fisherman's sight
+    Fog,
+    /// wind, bad catches, and gusts pushing the boat around
+    Storm,
+}
+
+impl Display for WeatherKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WeatherKind::Calm => write!(f, "calm"),
+            WeatherKind::Wind => write!(f, "wind"),
+            WeatherKind::Fog => write!(f, "fog"),
+            WeatherKind::Storm => write!(f, "storm"),
+        }
+    }
+}
+
+/// The weather of a level: spells of random weather, each lasting a random number of turns.
+/// It has its own rng, so the forecast only depends on the seed and not on what the player does.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Weather {
+    config: Option<WeatherConfig>,
+    kind: WeatherKind,
+    wind: HexDir,
+    // turns until the next spell
+    spell_left: usize,
+    rng: ChaCha8Rng,
+}
+
+impl Default for Weather {
+    fn default() -> Self {
+        Self {
+            config: None,
+            kind: WeatherKind::Calm,
+            wind: HexDir::ALL[0],
+            spell_left: 0,
+            rng: ChaCha8Rng::seed_from_u64(0),
+        }
+    }
+}
+
+impl Weather {
+    const SALT: u64 = 0x77656174686572;
+    const STORM_PUSH_CHANCE: f32 = 0.3;
+
+    pub fn new(config: &Option<WeatherConfig>, seed: u64) -> Self {
+        let Some(config) = config else {
+            return Self::default();
+        };
+        let mut weather = Self {
+            config: Some(*config),
+            rng: ChaCha8Rng::seed_from_u64(seed ^ Self::SALT),
+            ..Self::default()
+        };
+        weather.wind = HexDir::ALL[weather.rng.gen_range(0..6)];
+        weather.next_spell(config);
+        weather
+    }
+
+    fn next_spell(&mut self, config: &WeatherConfig) {
+        let roll = self.rng.gen::<f32>() * config.total();
+        let mut threshold = 0.0;
+        self.kind = WeatherKind::Storm;
+        for (odds, kind) in [(config.calm, WeatherKind::Calm), (config.wind, WeatherKind::Wind), (config.fog, WeatherKind::Fog)] {
+            threshold += odds;
+            if roll < threshold {
+                self.kind = kind;
+                break;
+            }
+        }
+        // the wind veers a little between spells
+        self.wind = self.wind.rotate(self.rng.gen_range(-1..=1));
+        self.spell_left = self.rng.gen_range(config.min_turns..=config.max_turns);
+    }
+
+    /// Moves on to the next turn, returns the new weather if it changed.
+    pub fn advance(&mut self) -> Option<WeatherKind> {
+        let config = self.config?;
+        self.spell_left = self.spell_left.saturating_sub(1);
+        if self.spell_left > 0 {
+            return None;
+        }
+        let kind = self.kind;
+        self.next_spell(&config);
+        (self.kind != kind).then_some(self.kind)
+    }
+
+    #[inline]
+    pub fn kind(&self) -> WeatherKind {
+        self.kind
+    }
+
+    /// Where the wind blows to, if it blows.
+    pub fn wind(&self) -> Option<HexDir> {
+        matches!(self.kind, WeatherKind::Wind | WeatherKind::Storm).then_some(self.wind)
+    }
+
+    /// Whether a gust pushes the boat this turn, and where to.
+    pub fn gust(&mut self) -> Option<HexDir> {
+        if self.kind != WeatherKind::Storm || self.rng.gen::<f32>() >= Self::STORM_PUSH_CHANCE {
+            return None;
+        }
+        Some(self.wind)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(calm: f32, wind: f32, fog: f32, storm: f32) -> Option<WeatherConfig> {
+        Some(WeatherConfig { calm, wind, fog, storm, min_turns: 2, max_turns: 4 })
+    }
+
+    #[test]
+    fn spells_last_between_min_and_max_turns() {
+        let mut weather = Weather::new(&config(1.0, 1.0, 1.0, 1.0), 9);
+        let mut lengths = vec![];
+        let mut length = weather.spell_left;
+        for _ in 0..1000 {
+            let before = weather.spell_left;
+            weather.advance();
+            if before == 1 {
+                lengths.push(length);
+                length = weather.spell_left;
+            }
+        }
+        assert!(lengths.iter().all(|l| (2..=4).contains(l)), "{:?}", lengths);
+        assert!(lengths.contains(&2) && lengths.contains(&4));
+    }
+
+    #[test]
+    fn advance_reports_only_changes() {
+        let mut weather = Weather::new(&config(1.0, 1.0, 1.0, 1.0), 9);
+        let mut changes = 0;
+        for _ in 0..1000 {
+            let kind = weather.kind();
+            let change = weather.advance();
+            assert_eq!(change, (weather.kind() != kind).then_some(weather.kind()));
+            changes += change.is_some() as usize;
+        }
+        assert!(changes > 0);
+    }
+
+    #[test]
+    fn storm_only_config_always_storms() {
+        let mut weather = Weather::new(&config(0.0, 0.0, 0.0, 1.0), 9);
+        for _ in 0..100 {
+            assert_eq!(weather.kind(), WeatherKind::Storm);
+            assert_eq!(weather.advance(), None);
+            assert!(weather.wind().is_some());
+        }
+    }
+
+    #[test]
+    fn no_config_stays_calm() {
+        let mut weather = Weather::new(&None, 9);
+        for _ in 0..100 {
+            assert_eq!(weather.advance(), None);
+            assert_eq!(weather.gust(), None);
+        }
+        assert_eq!(weather.kind(), WeatherKind::Calm);
+    }
+}
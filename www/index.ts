@@ -1,4 +1,4 @@
-import {Game, WasmUserAction, Fisherman, HexCoord, InputResult} from "../pkg"
+import {Game, WasmUserAction, Fisherman, HexCoord, InputResult, Replay, LevelScore, HighScores, TerrainType, TimeOfDay, WeatherKind, Upgrade} from "../pkg"
 import "./style.css"
 const UPDATE_RADIUS = 4;
 const MOVE_RADIUS = 1;
@@ -11,9 +11,87 @@ const binocular: HTMLButtonElement = document.querySelector("#binocular");
 const web: HTMLButtonElement = document.querySelector("#web");
 const compassAnalog: HTMLDivElement = document.querySelector("#compass-pointer");
 const compassDigital: HTMLDivElement = document.querySelector("#compass-digital");
+const logDisplay: HTMLDivElement = document.querySelector("#log");
+const levelDisplay: HTMLDivElement = document.querySelector("#level");
+const clockDisplay: HTMLDivElement = document.querySelector("#clock");
+const shopDisplay: HTMLDivElement = document.querySelector("#shop");
 let shiftHold: boolean = false;
 let isCapturing: boolean = false;
-let game = new Game();
+// `?seed=<n>` replays the same ocean, e.g. for daily challenges
+// `?campaign=<url>` plays the levels of a campaign file instead of the built-in ones
+const searchParams = new URLSearchParams(window.location.search);
+const seedParam = searchParams.get("seed");
+const campaignParam = searchParams.get("campaign");
+// `?replay=<url>` plays back a recorded game, one turn every REPLAY_DELAY ms
+const replayParam = searchParams.get("replay");
+const REPLAY_DELAY = 800;
+let campaignJson: string | null = null;
+function newGame(): Game {
+    if (campaignJson !== null) {
+        const seed = seedParam === null ? BigInt(Math.floor(Math.random() * Number.MAX_SAFE_INTEGER)) : BigInt(seedParam);
+        return Game.from_campaign(campaignJson, seed);
+    }
+    return seedParam === null ? new Game() : Game.with_seed(BigInt(seedParam));
+}
+// the game is saved to localStorage after every turn and can be resumed on the next visit
+const SAVE_KEY = "fisherman-save";
+function loadSavedGame(): Game | null {
+    const saved = localStorage.getItem(SAVE_KEY);
+    if (saved === null || !confirm("Resume your saved game?")) {
+        return null;
+    }
+    try {
+        return Game.load(saved);
+    } catch (e) {
+        alert(`Cannot load saved game: ${e}`);
+        localStorage.removeItem(SAVE_KEY);
+        return null;
+    }
+}
+let game = loadSavedGame() ?? newGame();
+// coins and, while in a harbor, a button for every upgrade on sale
+function renderShop(game: Game) {
+    shopDisplay.innerHTML = "";
+    const coins = document.createElement("div");
+    coins.innerText = `Coins: ${game.get_coins()}`;
+    shopDisplay.appendChild(coins);
+    if (!game.is_shop_open()) {
+        return;
+    }
+    for (const upgrade of [Upgrade.Net, Upgrade.Harpoon, Upgrade.Hull, Upgrade.Binoculars]) {
+        const price = game.get_upgrade_price(upgrade);
+        const button = document.createElement("button");
+        const name = Upgrade[upgrade].toLowerCase();
+        button.innerText = price === undefined ? `${name} (max)` : `${name} lv${game.get_upgrade_level(upgrade) + 1}: ${price}`;
+        button.disabled = price === undefined || price > game.get_coins();
+        button.addEventListener("click", (e) => {
+            e.preventDefault();
+            e.stopPropagation();
+            handleInputResult(game.handle_action(WasmUserAction.buy_action(upgrade)));
+        });
+        shopDisplay.appendChild(button);
+    }
+}
+// every finished run is added to the high scores kept in localStorage
+const SCORES_KEY = "fisherman-scores";
+function saveHighScore(game: Game) {
+    let scores = new HighScores();
+    const saved = localStorage.getItem(SCORES_KEY);
+    if (saved !== null) {
+        try {
+            scores = HighScores.from_json(saved);
+        } catch (e) {
+            alert(`Cannot load high scores, starting a new table: ${e}`);
+        }
+    }
+    const name = prompt("Your name for the high scores:", "fisherman");
+    if (name === null) {
+        return;
+    }
+    scores.add_game(game, name, BigInt(Math.floor(Date.now() / 1000)));
+    localStorage.setItem(SCORES_KEY, scores.to_json());
+    alert(`High scores\n${scores.get_top_lines(10).join("\n")}`);
+}
 
 function uniformSampleHexagon(height: number): number[] {
     const scale = height / Math.sqrt(3);
@@ -30,10 +108,53 @@ function uniformSampleHexagon(height: number): number[] {
     return [scale * (x * Math.cos(theta) + y * Math.sin(theta)), scale * (-x * Math.sin(theta) + y * Math.cos(theta))];
 }
 
+function describeScore(score: LevelScore): string {
+    return [
+        `Level ${score.level_index + 1}: ${score.total()} points`,
+        `  marlins: ${score.marlins} (+${score.marlin_points()})`,
+        `  surplus marlins: ${score.surplus} (+${score.surplus_points()})`,
+        `  HP left: ${score.hp_left} (+${score.hp_points()})`,
+        `  turns: ${score.turns} (+${score.turn_points()})`,
+        `  sharks killed: ${score.sharks_killed} (+${score.shark_points()})`,
+        `  undamaged catches: ${score.undamaged_captures} (+${score.undamaged_capture_points()})`,
+    ].join("\n");
+}
+
+// after a level is over: retry it, jump to a level reached before, or restart the campaign.
+// returns false if the player wants a new game instead
+function chooseLevel(message: string): boolean {
+    while (true) {
+        const choice = prompt(`${message}\nPlay level (1-${game.get_furthest_level() + 1}), "r" to restart the campaign, or cancel for a new game:`, `${game.get_level_index() + 1}`);
+        if (choice === null) {
+            return false;
+        }
+        if (choice.trim() == "r") {
+            game.restart_campaign();
+            return true;
+        }
+        // wasm-bindgen would turn NaN or a negative number into some other level
+        const index = Number(choice.trim()) - 1;
+        if (!Number.isInteger(index) || index < 0) {
+            message = `"${choice}" is not a level number`;
+            continue;
+        }
+        if (index == game.get_level_index()) {
+            game.retry_level();
+            return true;
+        }
+        try {
+            game.jump_to_level(index);
+            return true;
+        } catch (e) {
+            message = `${e}`;
+        }
+    }
+}
+
 function handleInputResult(t: InputResult) {
     switch (t) {
         case InputResult.InvalidInput:
-            alert("This action is not allowed in harbor!");
+            alert(game.get_last_error_message() ?? "Invalid action!");
             shiftHold = false;
             setNonCapturing();
             break;
@@ -41,26 +162,30 @@ function handleInputResult(t: InputResult) {
             if (!shiftHold) {
                 setNonCapturing();
             }
+            localStorage.setItem(SAVE_KEY, game.save());
             renderMap(game, gameMapContainer, UPDATE_RADIUS);
             break;
         case InputResult.LevelPassed:
-            alert(`Level Passed! score: ${game.get_score()}`)
+            alert(`Level Passed!\n${describeScore(game.get_score_of(game.get_level_index() - 1))}\ncampaign score: ${game.get_score()}`)
             shiftHold = false;
             setNonCapturing();
+            localStorage.setItem(SAVE_KEY, game.save());
             renderMap(game, gameMapContainer, UPDATE_RADIUS);
             break;
         case InputResult.GamePassed:
-            alert(`Congrats! You beat the game! score: ${game.get_score()}`)
-            shiftHold = false;
-            setNonCapturing();
-            game = new Game()
-            renderMap(game, gameMapContainer, UPDATE_RADIUS);
-            break;
         case InputResult.LevelFailed:
-            alert(`Game over! score: ${game.get_score()}`)
             shiftHold = false;
             setNonCapturing();
-            game = new Game();
+            const message = t == InputResult.GamePassed
+                ? `Congrats! You beat the game!\n${game.get_level_scores().map(describeScore).join("\n")}\ncampaign score: ${game.get_score()}`
+                : `${game.get_turns_left() === 0 ? "Out of time!" : "Game over!"} campaign score: ${game.get_score()}`;
+            if (chooseLevel(message)) {
+                localStorage.setItem(SAVE_KEY, game.save());
+            } else {
+                localStorage.removeItem(SAVE_KEY);
+                saveHighScore(game);
+                game = newGame();
+            }
             renderMap(game, gameMapContainer, UPDATE_RADIUS);
             break;
     }
@@ -72,7 +197,30 @@ function renderMap(game: Game, container: HTMLDivElement, radius: number) {
     const fisherman = game.get_fisherman();
     const coord = fisherman.get_coord();
 
+    const retries = game.get_retries_at(game.get_level_index());
+    levelDisplay.innerText = `Level ${game.get_level_index() + 1} / ${game.get_level_count()}` + (retries > 0 ? `, retry ${retries}` : "");
     targetDisplay.innerHTML = `Target: ${fisherman.get_captured_marlins()} / ${game.get_target()}`
+    // time of day and the deadline, urgent once it takes every turn left to get back
+    const night = game.get_time_of_day() == TimeOfDay.Night;
+    gameMapContainer.classList.toggle("night", night);
+    let clock = `Turn ${game.get_turn() + 1}, ${night ? "night" : "day"}`;
+    const untilChange = game.get_turns_until_dusk_or_dawn();
+    if (untilChange !== undefined) {
+        clock += ` (${untilChange} to ${night ? "dawn" : "dusk"})`;
+    }
+    const turnsLeft = game.get_turns_left();
+    if (turnsLeft !== undefined) {
+        clock += `, ${turnsLeft} turns left`;
+    }
+    // the weather, with an arrow pointing where the wind blows
+    const weather = WeatherKind[game.get_weather()].toLowerCase();
+    for (const kind of ["calm", "wind", "fog", "storm"]) {
+        gameMapContainer.classList.toggle(`weather-${kind}`, kind == weather);
+    }
+    const wind = game.get_wind();
+    clock += `, ${weather}` + (wind === undefined ? "" : ` ${["↑", "↗", "↘", "↓", "↙", "↖"][wind.index() ?? 0]}`);
+    clockDisplay.innerText = clock;
+    clockDisplay.classList.toggle("urgent", turnsLeft !== undefined && turnsLeft <= coord.distance(game.get_nearest_harbor()));
     hpDisplay.innerHTML = "";
     for (let i = 0; i < fisherman.get_hp(); i++) {
         const heart = document.createElement("span")
@@ -87,19 +235,27 @@ function renderMap(game: Game, container: HTMLDivElement, radius: number) {
         heart.innerText = "♥";
         hpDisplay.appendChild(heart)
     }
+    renderShop(game);
+    logDisplay.innerHTML = "";
+    for (const message of game.get_event_messages()) {
+        const line = document.createElement("div");
+        line.innerText = message;
+        logDisplay.appendChild(line);
+    }
     if (fisherman.get_captured_marlins() >= game.get_target()) {
         targetDisplay.classList.add("fulfilled");
     } else {
         targetDisplay.classList.remove("fulfilled");
     }
 
-    // calculate degree and distance to harbor
-    const dist = coord.distance(new HexCoord(0, 0, 0));
+    // calculate degree and distance to the nearest harbor
+    const harbor = game.get_nearest_harbor();
+    const dist = coord.distance(harbor);
     let deg;
     if (dist == 0) {
         deg = 0;
     } else {
-        const dir = new HexCoord(-coord.q, -coord.r, -coord.s);
+        const dir = new HexCoord(harbor.q - coord.q, harbor.r - coord.r, harbor.s - coord.s);
         const zero = dir.r;
         const sixty = dir.q;
         const x = zero + sixty / 2;
@@ -157,7 +313,7 @@ function renderMap(game: Game, container: HTMLDivElement, radius: number) {
                         handleInputResult(game.handle_action(WasmUserAction.capture_action(new HexCoord(dq, dr, ds))))
                     } else {
                         if (dq == 0 && dr == 0 && ds == 0) {
-                            if (q == 0 && r == 0 && s == 0) {
+                            if (game.is_harbor(new HexCoord(q, r, s))) {
                                 // in harbor, move to nowhere
                                 handleInputResult(game.handle_action(WasmUserAction.move_action(new HexCoord(dq, dr, ds))));
                             } else {
@@ -182,12 +338,24 @@ function renderMap(game: Game, container: HTMLDivElement, radius: number) {
             const r = dr + coord.r;
             const s = ds + coord.s;
             const absCoord = new HexCoord(q, r, s);
-            if (q == 0 && r == 0 && s == 0) {
+            if (game.is_harbor(absCoord)) {
                 // harbor
                 let harborElement = document.createElement("div");
                 harborElement.classList.add("harbor")
                 hexagon.appendChild(harborElement)
             } else {
+                hexagon.classList.add(`terrain-${TerrainType[game.get_terrain_at(absCoord)].toLowerCase()}`);
+                if (!game.is_visible(absCoord)) {
+                    hexagon.classList.add("out-of-sight");
+                }
+                const current = game.get_current_at(absCoord);
+                if (current !== undefined) {
+                    const arrow = document.createElement("div");
+                    arrow.classList.add("current-arrow");
+                    arrow.innerText = "↑";
+                    arrow.style.rotate = `${(current.index() ?? 0) * 60}deg`;
+                    hexagon.appendChild(arrow);
+                }
                 const numMarlins = game.get_discovered_marlin_num_at(absCoord);
                 const numSharks = game.get_shark_num_at(absCoord);
                 for (let i = 0; i < numMarlins; ++i) {
@@ -227,8 +395,7 @@ function renderMap(game: Game, container: HTMLDivElement, radius: number) {
         e.stopPropagation();
         if (!isCapturing) {
             const coord = fisherman.get_coord();
-            const {q, r, s} = coord;
-            if (q == 0 && r == 0 && s == 0) {
+            if (game.is_harbor(coord)) {
 
                 handleInputResult(game.handle_action(WasmUserAction.move_action(new HexCoord(0, 0, 0))))
             } else {
@@ -244,6 +411,19 @@ function renderMap(game: Game, container: HTMLDivElement, radius: number) {
 
 
 renderMap(game, gameMapContainer, UPDATE_RADIUS)
+if (campaignParam !== null) {
+    fetch(campaignParam)
+        .then((res) => res.text())
+        .then((json) => {
+            campaignJson = json;
+            game = newGame();
+            renderMap(game, gameMapContainer, UPDATE_RADIUS);
+        })
+        .catch((e) => {
+            campaignJson = null;
+            alert(`Cannot load campaign ${campaignParam}: ${e}`);
+        });
+}
 
 function setCapturing() {
     isCapturing = true;
@@ -278,4 +458,37 @@ web.addEventListener("click", () => {
     } else {
         setCapturing();
     }
-})
\ No newline at end of file
+})
+if (replayParam !== null) {
+    fetch(replayParam)
+        .then((res) => res.text())
+        .then((json) => {
+            const replay = Replay.from_json(json);
+            game = replay.new_game();
+            renderMap(game, gameMapContainer, UPDATE_RADIUS);
+            let turn = 0;
+            const timer = setInterval(() => {
+                try {
+                    replay.prepare_turn(game, turn);
+                } catch (e) {
+                    clearInterval(timer);
+                    alert(`${e}`);
+                    return;
+                }
+                if (turn >= replay.get_turn_count()) {
+                    clearInterval(timer);
+                    alert(`End of replay, score: ${game.get_score()}`);
+                    return;
+                }
+                const result = game.handle_action(replay.get_action_at(turn));
+                if (result != replay.get_result_at(turn)) {
+                    clearInterval(timer);
+                    alert(`Replay diverged at turn ${turn}`);
+                    return;
+                }
+                renderMap(game, gameMapContainer, UPDATE_RADIUS);
+                turn += 1;
+            }, REPLAY_DELAY);
+        })
+        .catch((e) => alert(`Cannot load replay ${replayParam}: ${e}`));
+}